@@ -1,5 +1,10 @@
+use std::rc::Rc;
+
 use crate::parser::{
-    Binary, Expr, ExprVisitor, Grouping, Literal, StmtVisitor, Unary, VarAssignment,
+    Binary, Call, Class, Compound, Conditional, Destructure, Export, Expr, ExprId, ExprVisitor,
+    Function, Get, Grouping, If, Import, Index, IndexSet, Interpolation, List, Literal, Logical,
+    Map, Match, Return, Set, Stmt, StmtVisitor, Super, Throw, Try, Tuple, Unary, VarAssignment,
+    VarDestructure, While,
 };
 use crate::token::{LiteralValue, Token};
 
@@ -22,7 +27,141 @@ impl StmtVisitor<String> for AstPrinter {
     }
 
     fn visit_variable(&self, var: &VarAssignment) -> String {
-        format!("{}", var.token.lexeme)
+        if var.constant {
+            format!("(const {})", var.token.lexeme)
+        } else {
+            var.token.lexeme.to_string()
+        }
+    }
+
+    fn visit_var_destructure(&self, stmt: &VarDestructure) -> String {
+        let keyword = if stmt.constant { "const" } else { "var" };
+        format!(
+            "({} {} = {})",
+            keyword,
+            stmt.target,
+            stmt.initializer.accept(self)
+        )
+    }
+
+    fn visit_block(&self, stmts: &[Stmt]) -> String {
+        let mut out = String::from("(block");
+        for stmt in stmts {
+            out.push(' ');
+            out.push_str(&stmt.accept(self));
+        }
+        out.push(')');
+        out
+    }
+
+    fn visit_if(&self, stmt: &If) -> String {
+        let mut out = format!(
+            "(if {} {}",
+            stmt.condition.accept(self),
+            stmt.then_branch.accept(self)
+        );
+        if let Some(else_branch) = &stmt.else_branch {
+            out.push(' ');
+            out.push_str(&else_branch.accept(self));
+        }
+        out.push(')');
+        out
+    }
+
+    fn visit_while(&self, stmt: &While) -> String {
+        let mut out = format!(
+            "(while {} {}",
+            stmt.condition.accept(self),
+            stmt.body.accept(self)
+        );
+        if let Some(increment) = &stmt.increment {
+            out.push(' ');
+            out.push_str(&increment.accept(self));
+        }
+        out.push(')');
+        out
+    }
+
+    fn visit_function(&self, stmt: &Rc<Function>) -> String {
+        let params: Vec<&str> = stmt.params.iter().map(|p| p.lexeme.as_str()).collect();
+        let mut out = format!("(fun {}({})", stmt.name.lexeme, params.join(" "));
+        for body_stmt in &stmt.body {
+            out.push(' ');
+            out.push_str(&body_stmt.accept(self));
+        }
+        out.push(')');
+        out
+    }
+
+    fn visit_class(&self, stmt: &Class) -> String {
+        let mut out = format!("(class {}", stmt.name.lexeme);
+        if let Some(superclass) = &stmt.superclass {
+            out.push_str(&format!(" < {}", superclass.accept(self)));
+        }
+        for method in &stmt.methods {
+            out.push(' ');
+            out.push_str(&self.visit_function(method));
+        }
+        out.push(')');
+        out
+    }
+
+    fn visit_break(&self, keyword: &Token) -> String {
+        keyword.lexeme.to_string()
+    }
+
+    fn visit_continue(&self, keyword: &Token) -> String {
+        keyword.lexeme.to_string()
+    }
+
+    fn visit_match(&self, stmt: &Match) -> String {
+        let mut out = format!("(match {}", stmt.value.accept(self));
+        for arm in &stmt.arms {
+            let patterns: Vec<String> = arm.patterns.iter().map(|p| p.to_string()).collect();
+            out.push_str(&format!(" ({}", patterns.join(" | ")));
+            if let Some(guard) = &arm.guard {
+                out.push_str(&format!(" if {}", guard.accept(self)));
+            }
+            out.push_str(&format!(" => {})", arm.body.accept(self)));
+        }
+        out.push(')');
+        out
+    }
+
+    fn visit_throw(&self, stmt: &Throw) -> String {
+        format!("(throw {})", stmt.value.accept(self))
+    }
+
+    fn visit_try(&self, stmt: &Try) -> String {
+        let mut out = format!("(try {}", self.visit_block(&stmt.body));
+        if let Some(catch) = &stmt.catch {
+            out.push_str(&format!(" (catch {}", catch.name.lexeme));
+            for body_stmt in &catch.body {
+                out.push(' ');
+                out.push_str(&body_stmt.accept(self));
+            }
+            out.push(')');
+        }
+        if let Some(finally) = &stmt.finally {
+            out.push_str(&format!(" (finally {})", self.visit_block(finally)));
+        }
+        out.push(')');
+        out
+    }
+
+    fn visit_import(&self, stmt: &Import) -> String {
+        format!("(import \"{}\" as {})", stmt.path, stmt.name.lexeme)
+    }
+
+    fn visit_export(&self, stmt: &Export) -> String {
+        format!("(export {})", stmt.declaration.accept(self))
+    }
+
+    fn visit_return(&self, stmt: &Return) -> String {
+        match &stmt.value {
+            Some(value) => format!("(return {})", value.accept(self)),
+            None => "(return)".to_string(),
+        }
     }
 }
 
@@ -36,18 +175,157 @@ impl ExprVisitor<String> for AstPrinter {
         )
     }
 
-    fn visit_variable(&self, token: &Token) -> String {
-        format!("{}", token.lexeme)
+    fn visit_logical(&self, expr: &Logical) -> String {
+        format!(
+            "({} {} {})",
+            expr.operator,
+            expr.left.accept(self),
+            expr.right.accept(self)
+        )
+    }
+
+    fn visit_conditional(&self, expr: &Conditional) -> String {
+        format!(
+            "(?: {} {} {})",
+            expr.condition.accept(self),
+            expr.then_branch.accept(self),
+            expr.else_branch.accept(self)
+        )
+    }
+
+    fn visit_lambda(&self, function: &Rc<Function>) -> String {
+        let params: Vec<&str> = function.params.iter().map(|p| p.lexeme.as_str()).collect();
+        let mut out = format!("(lambda ({})", params.join(" "));
+        for body_stmt in &function.body {
+            out.push(' ');
+            out.push_str(&body_stmt.accept(self));
+        }
+        out.push(')');
+        out
+    }
+
+    fn visit_call(&self, expr: &Call) -> String {
+        let mut out = format!("(call {}", expr.callee.accept(self));
+        for argument in &expr.arguments {
+            out.push(' ');
+            out.push_str(&argument.accept(self));
+        }
+        out.push(')');
+        out
+    }
+
+    fn visit_variable(&self, _id: ExprId, token: &Token) -> String {
+        token.lexeme.to_string()
     }
 
     fn visit_unary(&self, expr: &Unary) -> String {
         format!("({} {})", expr.operator, expr.right.accept(self))
     }
 
-    fn visit_assign(&self, token: &Token, value: &Box<Expr>) -> String {
+    fn visit_assign(&self, _id: ExprId, token: &Token, value: &Box<Expr>) -> String {
         format!("{} = {}", token.lexeme, value.accept(self))
     }
 
+    fn visit_get(&self, expr: &Get) -> String {
+        format!("(. {} {})", expr.object.accept(self), expr.name.lexeme)
+    }
+
+    fn visit_set(&self, expr: &Set) -> String {
+        format!(
+            "(set {} {} = {})",
+            expr.object.accept(self),
+            expr.name.lexeme,
+            expr.value.accept(self)
+        )
+    }
+
+    fn visit_this(&self, _id: ExprId, keyword: &Token) -> String {
+        keyword.lexeme.to_string()
+    }
+
+    fn visit_super(&self, expr: &Super) -> String {
+        format!("(super {})", expr.method.lexeme)
+    }
+
+    fn visit_list(&self, expr: &List) -> String {
+        let mut out = String::from("(list");
+        for element in &expr.elements {
+            out.push(' ');
+            out.push_str(&element.accept(self));
+        }
+        out.push(')');
+        out
+    }
+
+    fn visit_index(&self, expr: &Index) -> String {
+        format!(
+            "(index {} {})",
+            expr.object.accept(self),
+            expr.index.accept(self)
+        )
+    }
+
+    fn visit_index_set(&self, expr: &IndexSet) -> String {
+        format!(
+            "(index-set {} {} = {})",
+            expr.object.accept(self),
+            expr.index.accept(self),
+            expr.value.accept(self)
+        )
+    }
+
+    fn visit_tuple(&self, expr: &Tuple) -> String {
+        let mut out = String::from("(tuple");
+        for element in &expr.elements {
+            out.push(' ');
+            out.push_str(&element.accept(self));
+        }
+        out.push(')');
+        out
+    }
+
+    fn visit_destructure(&self, expr: &Destructure) -> String {
+        format!("{} = {}", expr.target, expr.value.accept(self))
+    }
+
+    fn visit_compound(&self, expr: &Compound) -> String {
+        let target = expr.target.accept(self);
+        match expr.operator.lexeme.as_str() {
+            "++" | "--" if expr.postfix => format!("({} {})", target, expr.operator.lexeme),
+            "++" | "--" => format!("({} {})", expr.operator.lexeme, target),
+            _ => format!(
+                "({} {} {})",
+                expr.operator.lexeme,
+                target,
+                expr.value.accept(self)
+            ),
+        }
+    }
+
+    fn visit_map(&self, expr: &Map) -> String {
+        let mut out = String::from("(map");
+        for (key, value) in &expr.entries {
+            out.push_str(&format!(" ({} {})", key.accept(self), value.accept(self)));
+        }
+        out.push(')');
+        out
+    }
+
+    fn visit_interpolation(&self, expr: &Interpolation) -> String {
+        let mut out = String::from("(interpolate");
+        for part in &expr.parts {
+            out.push(' ');
+            match part {
+                Expr::Literal(Literal {
+                    value: LiteralValue::String(s),
+                }) => out.push_str(&format!("\"{}\"", s)),
+                _ => out.push_str(&part.accept(self)),
+            }
+        }
+        out.push(')');
+        out
+    }
+
     fn visit_grouping(&self, expr: &Grouping) -> String {
         format!("(group {})", expr.expr.accept(self))
     }
@@ -58,11 +336,13 @@ impl ExprVisitor<String> for AstPrinter {
             LiteralValue::String(s) => s.clone(),
             LiteralValue::Boolean(b) => b.to_string(),
             LiteralValue::Nil => "nil".to_string(),
+            value => value.to_string(),
         }
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::unnecessary_mut_passed)]
 mod tests {
     use super::*;
     use crate::parser::Expr;
@@ -0,0 +1,146 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::intrepreter::{Environment, Interpreter, RuntimeError, Unwind};
+use crate::parser::Function;
+use crate::token::LiteralValue;
+
+/// A runtime value that can be invoked with `callee(arguments...)`.
+pub trait Callable {
+    fn arity(&self) -> usize;
+    /// Invoke the callable. `line` is the call site, used for errors raised by the call itself.
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<LiteralValue>,
+        line: usize,
+    ) -> Result<LiteralValue, RuntimeError>;
+}
+
+/// A user-defined function together with the environment it was declared in.
+pub struct LoxFunction {
+    declaration: Rc<Function>,
+    closure: Rc<RefCell<Environment>>,
+    /// Class `init` methods always return `this`, even from a bare `return;`.
+    is_initializer: bool,
+}
+
+impl LoxFunction {
+    pub fn new(
+        declaration: Rc<Function>,
+        closure: Rc<RefCell<Environment>>,
+        is_initializer: bool,
+    ) -> Self {
+        LoxFunction {
+            declaration,
+            closure,
+            is_initializer,
+        }
+    }
+
+    /// Produce a copy of this method whose closure binds `this` to `instance`.
+    pub fn bind(&self, instance: LiteralValue) -> LoxFunction {
+        let mut environment = Environment::with_enclosing(Rc::clone(&self.closure));
+        environment.define("this", instance);
+        LoxFunction::new(
+            Rc::clone(&self.declaration),
+            Rc::new(RefCell::new(environment)),
+            self.is_initializer,
+        )
+    }
+
+    fn this(&self) -> LiteralValue {
+        self.closure
+            .borrow()
+            .get("this")
+            .unwrap_or(LiteralValue::Nil)
+    }
+}
+
+impl Callable for LoxFunction {
+    fn arity(&self) -> usize {
+        self.declaration.params.len()
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<LiteralValue>,
+        _line: usize,
+    ) -> Result<LiteralValue, RuntimeError> {
+        let mut environment = Environment::with_enclosing(Rc::clone(&self.closure));
+        for (param, argument) in self.declaration.params.iter().zip(arguments) {
+            environment.define(&param.lexeme, argument);
+        }
+
+        match interpreter.execute_block(&self.declaration.body, Rc::new(RefCell::new(environment)))
+        {
+            Ok(()) | Err(Unwind::Return(_)) if self.is_initializer => Ok(self.this()),
+            // The resolver keeps `break` and `continue` from escaping a function body.
+            Ok(()) | Err(Unwind::Break | Unwind::Continue) => Ok(LiteralValue::Nil),
+            Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Error(e)) => Err(e),
+        }
+    }
+}
+
+impl fmt::Display for LoxFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<fn {}>", self.declaration.name.lexeme)
+    }
+}
+
+impl fmt::Debug for LoxFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+/// Signature of host-provided builtins: positional arguments in, a value or error out.
+pub type NativeFn = dyn Fn(&[LiteralValue]) -> Result<LiteralValue, RuntimeError>;
+
+/// A builtin implemented in Rust and registered with `Interpreter::register_native`.
+pub struct NativeFunction {
+    name: String,
+    arity: usize,
+    function: Box<NativeFn>,
+}
+
+impl NativeFunction {
+    pub fn new(name: &str, arity: usize, function: Box<NativeFn>) -> Self {
+        NativeFunction {
+            name: name.to_string(),
+            arity,
+            function,
+        }
+    }
+}
+
+impl Callable for NativeFunction {
+    fn arity(&self) -> usize {
+        self.arity
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: Vec<LiteralValue>,
+        line: usize,
+    ) -> Result<LiteralValue, RuntimeError> {
+        // Natives don't know where they were called from, so errors get the call site's line.
+        (self.function)(&arguments).map_err(|e| RuntimeError { line, ..e })
+    }
+}
+
+impl fmt::Display for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+impl fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
@@ -0,0 +1,136 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::callable::{Callable, LoxFunction};
+use crate::intrepreter::{Interpreter, RuntimeError};
+use crate::token::{LiteralValue, Token};
+
+/// A class declared in Lox: its methods plus an optional superclass to fall back to.
+pub struct LoxClass {
+    name: String,
+    superclass: Option<Rc<LoxClass>>,
+    methods: HashMap<String, Rc<LoxFunction>>,
+}
+
+impl LoxClass {
+    pub fn new(
+        name: &str,
+        superclass: Option<Rc<LoxClass>>,
+        methods: HashMap<String, Rc<LoxFunction>>,
+    ) -> Self {
+        LoxClass {
+            name: name.to_string(),
+            superclass,
+            methods,
+        }
+    }
+
+    /// Look up a method on this class, then up the inheritance chain.
+    pub fn find_method(&self, name: &str) -> Option<Rc<LoxFunction>> {
+        match self.methods.get(name) {
+            Some(method) => Some(Rc::clone(method)),
+            None => self.superclass.as_ref()?.find_method(name),
+        }
+    }
+}
+
+/// Calling a class constructs an instance and runs its `init` method, if any.
+impl Callable for Rc<LoxClass> {
+    fn arity(&self) -> usize {
+        self.find_method("init")
+            .map(|initializer| initializer.arity())
+            .unwrap_or(0)
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<LiteralValue>,
+        line: usize,
+    ) -> Result<LiteralValue, RuntimeError> {
+        let instance =
+            LiteralValue::Instance(Rc::new(RefCell::new(LoxInstance::new(Rc::clone(self)))));
+        if let Some(initializer) = self.find_method("init") {
+            initializer
+                .bind(instance.clone())
+                .call(interpreter, arguments, line)?;
+        }
+        Ok(instance)
+    }
+}
+
+impl fmt::Display for LoxClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl fmt::Debug for LoxClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+/// An instance of a `LoxClass` with its own mutable fields.
+pub struct LoxInstance {
+    class: Rc<LoxClass>,
+    fields: HashMap<String, LiteralValue>,
+}
+
+impl LoxInstance {
+    pub fn new(class: Rc<LoxClass>) -> Self {
+        LoxInstance {
+            class,
+            fields: HashMap::new(),
+        }
+    }
+
+    /// Read a property: fields shadow methods, and methods come back bound to `instance`.
+    pub fn get(
+        instance: &Rc<RefCell<LoxInstance>>,
+        name: &Token,
+    ) -> Result<LiteralValue, RuntimeError> {
+        let this = instance.borrow();
+        if let Some(value) = this.fields.get(&name.lexeme) {
+            return Ok(value.clone());
+        }
+
+        match this.class.find_method(&name.lexeme) {
+            Some(method) => Ok(LiteralValue::Function(Rc::new(
+                method.bind(LiteralValue::Instance(Rc::clone(instance))),
+            ))),
+            None => Err(RuntimeError::undefined_property(
+                &name.lexeme,
+                &this.class.name,
+                name.line,
+            )),
+        }
+    }
+
+    pub fn set(&mut self, name: &Token, value: LiteralValue) {
+        self.set_field(&name.lexeme, value);
+    }
+
+    /// Read a field directly, without falling back to methods.
+    pub fn field(&self, name: &str) -> Option<LiteralValue> {
+        self.fields.get(name).cloned()
+    }
+
+    pub fn set_field(&mut self, name: &str, value: LiteralValue) {
+        self.fields.insert(name.to_string(), value);
+    }
+}
+
+impl fmt::Display for LoxInstance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} instance", self.class.name)
+    }
+}
+
+impl fmt::Debug for LoxInstance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
@@ -1,16 +1,40 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
+use crate::callable::{Callable, LoxFunction, NativeFunction};
+use crate::class::{LoxClass, LoxInstance};
+use crate::map::{LoxMap, MapKey};
+use crate::module::{self, LoxModule};
+use crate::natives;
 use crate::parser::{
-    Binary, Expr, ExprVisitorMut, Grouping, Literal, Stmt, StmtVisitorMut, Unary, VarAssignment,
+    Binary, Call, Class, Compound, Conditional, Destructure, Export, Expr, ExprId, ExprVisitorMut,
+    Function, Get, Grouping, If, Import, Index, IndexSet, Interpolation, List, Literal, Logical,
+    Map, Match, Pattern, Return, Set, Stmt, StmtVisitorMut, Super, Target, Throw, Try, Tuple,
+    Unary, VarAssignment, VarDestructure, While,
 };
 use crate::token::{LiteralValue, Token, TokenType};
 
 pub struct RuntimeError {
     pub message: String,
     pub line: usize,
+    /// The value passed to `throw`, or `None` for errors raised by the interpreter itself.
+    pub thrown: Option<LiteralValue>,
 }
 
 impl RuntimeError {
+    /// An error raised by a native function. The interpreter fills in the line of the call.
+    pub fn native(message: &str) -> Self {
+        RuntimeError {
+            message: message.to_string(),
+            line: 0,
+            thrown: None,
+        }
+    }
+
     fn invalid_operands(
         left: LiteralValue,
         right: LiteralValue,
@@ -25,6 +49,7 @@ impl RuntimeError {
                 message
             ),
             line: token.line,
+            thrown: None,
         }
     }
 
@@ -32,6 +57,53 @@ impl RuntimeError {
         RuntimeError {
             message: format!("Invalid operator: {:?}", token_type),
             line: token.line,
+            thrown: None,
+        }
+    }
+
+    fn not_destructurable(value: &LiteralValue, line: usize) -> Self {
+        RuntimeError {
+            message: format!(
+                "Only tuples and lists can be destructured, got {}.",
+                format_literal(value)
+            ),
+            line,
+            thrown: None,
+        }
+    }
+
+    fn destructure_count(expected: usize, actual: usize, line: usize) -> Self {
+        RuntimeError {
+            message: format!(
+                "Expected {} values to destructure but got {}.",
+                expected, actual
+            ),
+            line,
+            thrown: None,
+        }
+    }
+
+    fn assign_to_constant(name: &str, line: usize) -> Self {
+        RuntimeError {
+            message: format!("Can't assign to constant '{}'.", name),
+            line,
+            thrown: None,
+        }
+    }
+
+    fn assign_to_builtin(name: &str, line: usize) -> Self {
+        RuntimeError {
+            message: format!("Can't assign to builtin '{}'.", name),
+            line,
+            thrown: None,
+        }
+    }
+
+    fn redeclared_constant(name: &str, line: usize) -> Self {
+        RuntimeError {
+            message: format!("Can't redeclare constant '{}'.", name),
+            line,
+            thrown: None,
         }
     }
 
@@ -39,90 +111,825 @@ impl RuntimeError {
         RuntimeError {
             message: format!("Undefined variable: {}", name),
             line,
+            thrown: None,
+        }
+    }
+
+    fn not_callable(callee: &LiteralValue, line: usize) -> Self {
+        RuntimeError {
+            message: format!(
+                "Can only call functions and classes, got {}.",
+                format_literal(callee)
+            ),
+            line,
+            thrown: None,
+        }
+    }
+
+    fn arity_mismatch(expected: usize, got: usize, line: usize) -> Self {
+        RuntimeError {
+            message: format!("Expected {} arguments but got {}.", expected, got),
+            line,
+            thrown: None,
+        }
+    }
+
+    pub fn undefined_property(name: &str, class: &str, line: usize) -> Self {
+        RuntimeError {
+            message: format!("Undefined property '{}' on {} instance.", name, class),
+            line,
+            thrown: None,
+        }
+    }
+
+    fn not_an_instance(object: &LiteralValue, what: &str, line: usize) -> Self {
+        RuntimeError {
+            message: format!(
+                "Only instances have {}, got {}.",
+                what,
+                format_literal(object)
+            ),
+            line,
+            thrown: None,
+        }
+    }
+
+    fn not_indexable(object: &LiteralValue, line: usize) -> Self {
+        RuntimeError {
+            message: format!(
+                "Only lists and maps can be indexed, got {}.",
+                format_literal(object)
+            ),
+            line,
+            thrown: None,
+        }
+    }
+
+    fn invalid_index(index: &LiteralValue, line: usize) -> Self {
+        RuntimeError {
+            message: format!(
+                "List index must be a non-negative integer, got {}.",
+                format_literal(index)
+            ),
+            line,
+            thrown: None,
+        }
+    }
+
+    fn index_out_of_range(index: &LiteralValue, len: usize, line: usize) -> Self {
+        RuntimeError {
+            message: format!(
+                "List index {} is out of range for a list of length {}.",
+                format_literal(index),
+                len
+            ),
+            line,
+            thrown: None,
+        }
+    }
+
+    pub fn unhashable_key(key: &LiteralValue, line: usize) -> Self {
+        RuntimeError {
+            message: format!(
+                "Map keys must be numbers, strings, booleans or nil, got {}.",
+                format_literal(key)
+            ),
+            line,
+            thrown: None,
+        }
+    }
+
+    fn missing_key(key: &LiteralValue, line: usize) -> Self {
+        RuntimeError {
+            message: format!("Key {} not found in map.", format_literal(key)),
+            line,
+            thrown: None,
+        }
+    }
+
+    fn division_by_zero(operation: &str, line: usize) -> Self {
+        RuntimeError {
+            message: format!("{} by zero.", operation),
+            line,
+            thrown: None,
+        }
+    }
+
+    pub fn not_exported(name: &str, module: &str, line: usize) -> Self {
+        RuntimeError {
+            message: format!("Module '{}' has no export named '{}'.", module, name),
+            line,
+            thrown: None,
+        }
+    }
+
+    fn module_error(message: String, line: usize) -> Self {
+        RuntimeError {
+            message,
+            line,
+            thrown: None,
+        }
+    }
+
+    /// A state the resolver should have ruled out; reported rather than papered over.
+    fn unresolved(name: &str, line: usize) -> Self {
+        RuntimeError {
+            message: format!("Internal error: '{}' was not resolved.", name),
+            line,
+            thrown: None,
+        }
+    }
+
+    fn thrown(value: LiteralValue, line: usize) -> Self {
+        // Rethrowing a caught error keeps its original message.
+        let message = match &value {
+            LiteralValue::String(s) => s.clone(),
+            LiteralValue::Instance(instance) => match instance.borrow().field("message") {
+                Some(LiteralValue::String(s)) => s,
+                _ => value.to_string(),
+            },
+            other => other.to_string(),
+        };
+        RuntimeError {
+            message,
+            line,
+            thrown: Some(value),
+        }
+    }
+
+    fn superclass_not_a_class(superclass: &LiteralValue, line: usize) -> Self {
+        RuntimeError {
+            message: format!(
+                "Superclass must be a class, got {}.",
+                format_literal(superclass)
+            ),
+            line,
+            thrown: None,
         }
     }
 }
 
+/// Why execution of a statement stopped early: a runtime error, a `return` carrying its
+/// value back to the enclosing call, or a `break` / `continue` headed for the nearest loop.
+pub enum Unwind {
+    Error(RuntimeError),
+    Return(LiteralValue),
+    Break,
+    Continue,
+}
+
+impl From<RuntimeError> for Unwind {
+    fn from(error: RuntimeError) -> Self {
+        Unwind::Error(error)
+    }
+}
+
 fn format_literal(literal: &LiteralValue) -> String {
     match literal {
         LiteralValue::Number(n) => n.to_string(),
         LiteralValue::String(s) => format!("\"{}\"", s),
         LiteralValue::Boolean(b) => b.to_string(),
         LiteralValue::Nil => "nil".to_string(),
+        LiteralValue::Function(function) => function.to_string(),
+        LiteralValue::NativeFunction(function) => function.to_string(),
+        LiteralValue::Class(class) => class.to_string(),
+        LiteralValue::Instance(instance) => instance.borrow().to_string(),
+        LiteralValue::List(_)
+        | LiteralValue::Map(_)
+        | LiteralValue::Module(_)
+        | LiteralValue::Tuple(_) => literal.to_string(),
+    }
+}
+
+fn pattern_matches(pattern: &Pattern, value: &LiteralValue) -> bool {
+    match (pattern, value) {
+        (Pattern::Wildcard, _) => true,
+        (Pattern::Literal(literal), value) => literal == value,
+        (
+            Pattern::Range {
+                start,
+                end,
+                inclusive,
+            },
+            LiteralValue::Number(n),
+        ) => *start <= *n && (*n < *end || (*inclusive && *n == *end)),
+        (Pattern::Range { .. }, _) => false,
+    }
+}
+
+/// Match `value` against `target`, collecting the value each variable receives. Every
+/// count is checked before anything is bound, so a mismatch leaves all variables as they were.
+fn unpack<'t>(
+    target: &'t Target,
+    value: LiteralValue,
+    bindings: &mut Vec<(ExprId, &'t Token, LiteralValue)>,
+) -> Result<(), RuntimeError> {
+    match target {
+        Target::Variable { id, name } => bindings.push((*id, name, value)),
+        Target::Tuple { paren, elements } => {
+            let values = match &value {
+                LiteralValue::Tuple(values) => values.as_ref().clone(),
+                LiteralValue::List(values) => values.borrow().clone(),
+                other => return Err(RuntimeError::not_destructurable(other, paren.line)),
+            };
+            if values.len() != elements.len() {
+                return Err(RuntimeError::destructure_count(
+                    elements.len(),
+                    values.len(),
+                    paren.line,
+                ));
+            }
+            for (element, value) in elements.iter().zip(values) {
+                unpack(element, value, bindings)?;
+            }
+        }
     }
+    Ok(())
 }
 
+/// Read `object[index]` from a list or map.
+fn index_get(
+    object: &LiteralValue,
+    index: &LiteralValue,
+    line: usize,
+) -> Result<LiteralValue, RuntimeError> {
+    match object {
+        LiteralValue::List(list) => {
+            let list = list.borrow();
+            let i = list_index(index, list.len(), line)?;
+            Ok(list[i].clone())
+        }
+        LiteralValue::Map(map) => {
+            let key = MapKey::new(index, line)?;
+            map.borrow()
+                .get(&key)
+                .cloned()
+                .ok_or_else(|| RuntimeError::missing_key(index, line))
+        }
+        other => Err(RuntimeError::not_indexable(other, line)),
+    }
+}
+
+/// Store `value` at `object[index]`. Lists must already have the slot; maps grow as needed.
+fn index_set(
+    object: &LiteralValue,
+    index: LiteralValue,
+    value: LiteralValue,
+    line: usize,
+) -> Result<(), RuntimeError> {
+    match object {
+        LiteralValue::List(list) => {
+            let mut list = list.borrow_mut();
+            let i = list_index(&index, list.len(), line)?;
+            list[i] = value;
+        }
+        LiteralValue::Map(map) => {
+            let key = MapKey::new(&index, line)?;
+            map.borrow_mut().insert(key, index, value);
+        }
+        other => return Err(RuntimeError::not_indexable(other, line)),
+    }
+    Ok(())
+}
+
+/// Check that `index` addresses an existing element of a list of length `len`.
+fn list_index(index: &LiteralValue, len: usize, line: usize) -> Result<usize, RuntimeError> {
+    let n = match index {
+        LiteralValue::Number(n) if *n >= 0.0 && n.fract() == 0.0 => *n,
+        other => return Err(RuntimeError::invalid_index(other, line)),
+    };
+    // Compare before casting, since the cast saturates huge indices to `usize::MAX`.
+    if n >= len as f64 {
+        return Err(RuntimeError::index_out_of_range(index, len, line));
+    }
+    Ok(n as usize)
+}
+
+/// Builtin classes written in Lox, run into the builtins scope by `Interpreter::new`.
+const PRELUDE: &str = "class Error { init(message) { this.message = message; } }";
+
 #[derive(Debug)]
 pub struct Environment {
     values: HashMap<String, LiteralValue>,
+    /// Names in `values` that were declared with `const`.
+    constants: HashSet<String>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+/// Outcome of updating an existing binding.
+#[derive(Debug, PartialEq)]
+pub enum Assignment {
+    Assigned,
+    Undefined,
+    Constant,
 }
 
 pub struct Interpreter {
-    environment: Environment,
+    /// Native functions and the `Error` class, shared by the script and every module.
+    builtins: Rc<RefCell<Environment>>,
+    environment: Rc<RefCell<Environment>>,
+    /// Scope depth of each resolved local variable reference; anything else is a global.
+    locals: HashMap<ExprId, usize>,
+    /// Modules that finished loading, by canonical path.
+    modules: HashMap<PathBuf, Rc<LoxModule>>,
+    /// Modules currently being loaded, outermost first, for cycle detection.
+    importing: Vec<PathBuf>,
+    /// Directory that `import` paths are relative to.
+    base_dir: PathBuf,
 }
 
 impl Environment {
     pub fn new() -> Self {
         Environment {
             values: HashMap::new(),
+            constants: HashSet::new(),
+            enclosing: None,
+        }
+    }
+
+    /// Create a nested scope whose lookups fall back to `enclosing`.
+    pub fn with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
+        Environment {
+            values: HashMap::new(),
+            constants: HashSet::new(),
+            enclosing: Some(enclosing),
         }
     }
 }
 
 impl Environment {
-    pub fn get(&self, name: &String) -> Option<LiteralValue> {
-        self.values.get(name).cloned()
+    /// Bind `name` in this scope, shadowing any binding in an enclosing one.
+    pub fn define(&mut self, name: &str, value: LiteralValue) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    /// Bind `name` in this scope so that later assignments to it fail.
+    pub fn define_const(&mut self, name: &str, value: LiteralValue) {
+        self.values.insert(name.to_string(), value);
+        self.constants.insert(name.to_string());
+    }
+
+    /// Whether declaring `name` in this scope would replace or become a constant that
+    /// shares its name with another binding.
+    fn conflicts_with_constant(&self, name: &str, constant: bool) -> bool {
+        self.constants.contains(name) || (constant && self.values.contains_key(name))
+    }
+
+    /// Overwrite the binding of `name` in this scope, unless it is a constant.
+    fn assign_here(&mut self, name: &str, value: LiteralValue) -> Assignment {
+        if self.constants.contains(name) {
+            return Assignment::Constant;
+        }
+        match self.values.get_mut(name) {
+            Some(slot) => {
+                *slot = value;
+                Assignment::Assigned
+            }
+            None => Assignment::Undefined,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<LiteralValue> {
+        match self.values.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.enclosing.as_ref()?.borrow().get(name),
+        }
+    }
+
+    /// Update the innermost existing binding of `name`.
+    pub fn assign(&mut self, name: &str, value: LiteralValue) -> Assignment {
+        if self.values.contains_key(name) {
+            return self.assign_here(name, value);
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign(name, value),
+            None => Assignment::Undefined,
+        }
+    }
+
+    /// Read `name` from exactly `distance` scopes out, as computed by the resolver.
+    pub fn get_at(&self, distance: usize, name: &str) -> Option<LiteralValue> {
+        if distance == 0 {
+            return self.values.get(name).cloned();
+        }
+        self.enclosing.as_ref()?.borrow().get_at(distance - 1, name)
+    }
+
+    /// Update `name` exactly `distance` scopes out, as computed by the resolver.
+    pub fn assign_at(&mut self, distance: usize, name: &str, value: LiteralValue) -> Assignment {
+        if distance == 0 {
+            return self.assign_here(name, value);
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign_at(distance - 1, name, value),
+            None => Assignment::Undefined,
+        }
     }
 }
 
 impl Interpreter {
     pub fn new() -> Self {
-        Interpreter {
-            environment: Environment::new(),
+        let builtins = Rc::new(RefCell::new(Environment::new()));
+        // The script's top-level scope; each module gets its own over the same builtins.
+        let globals = Environment::with_enclosing(Rc::clone(&builtins));
+        let mut interpreter = Interpreter {
+            builtins,
+            environment: Rc::new(RefCell::new(globals)),
+            locals: HashMap::new(),
+            modules: HashMap::new(),
+            importing: Vec::new(),
+            base_dir: PathBuf::from("."),
+        };
+        interpreter.run_prelude();
+        natives::register_builtins(&mut interpreter);
+        interpreter
+    }
+
+    fn run_prelude(&mut self) {
+        let (statements, locals) =
+            module::compile(PRELUDE, "<prelude>").expect("the prelude should compile");
+        self.resolve(locals);
+        let globals = mem::replace(&mut self.environment, Rc::clone(&self.builtins));
+        if let Err(e) = self.interpret(&statements) {
+            panic!("The prelude failed to run: {}", e.message);
+        }
+        self.environment = globals;
+    }
+
+    /// The builtin `Error` class; runtime errors are caught as instances of it.
+    fn error_class(&self) -> Rc<LoxClass> {
+        match self.builtins.borrow().get("Error") {
+            Some(LiteralValue::Class(class)) => class,
+            _ => unreachable!("the prelude defines Error"),
         }
     }
 
+    /// Expose a Rust closure to scripts as the global function `name`.
+    pub fn register_native<F>(&mut self, name: &str, arity: usize, function: F)
+    where
+        F: Fn(&[LiteralValue]) -> Result<LiteralValue, RuntimeError> + 'static,
+    {
+        let native = NativeFunction::new(name, arity, Box::new(function));
+        self.builtins
+            .borrow_mut()
+            .define(name, LiteralValue::NativeFunction(Rc::new(native)));
+    }
+
+    /// Resolve `import` paths relative to `dir`, normally the directory of the script.
+    pub fn set_base_dir(&mut self, dir: &Path) {
+        self.base_dir = dir.to_path_buf();
+    }
+
+    /// The top-level scope of the module whose code is running: the outermost scope of
+    /// the current environment below the builtins. Unresolved names live here.
+    fn module_globals(&self) -> Rc<RefCell<Environment>> {
+        let mut environment = Rc::clone(&self.environment);
+        loop {
+            let enclosing = match &environment.borrow().enclosing {
+                Some(enclosing) if !Rc::ptr_eq(enclosing, &self.builtins) => Rc::clone(enclosing),
+                _ => break,
+            };
+            environment = enclosing;
+        }
+        environment
+    }
+
+    /// Load, run and cache the module at `path`, or return the cached copy.
+    fn import_module(&mut self, path: &str, line: usize) -> Result<Rc<LoxModule>, RuntimeError> {
+        let canonical = self.base_dir.join(path).canonicalize().map_err(|e| {
+            RuntimeError::module_error(format!("Can't open module '{}': {}.", path, e), line)
+        })?;
+        if let Some(module) = self.modules.get(&canonical) {
+            return Ok(Rc::clone(module));
+        }
+        if let Some(start) = self.importing.iter().position(|p| *p == canonical) {
+            let cycle: Vec<String> = self.importing[start..]
+                .iter()
+                .chain([&canonical])
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(RuntimeError::module_error(
+                format!("Import cycle: {}.", cycle.join(" -> ")),
+                line,
+            ));
+        }
+
+        let source = fs::read_to_string(&canonical).map_err(|e| {
+            RuntimeError::module_error(format!("Can't read module '{}': {}.", path, e), line)
+        })?;
+        let (statements, locals) =
+            module::compile(&source, path).map_err(|e| RuntimeError::module_error(e, line))?;
+        self.resolve(locals);
+
+        let environment = Rc::new(RefCell::new(Environment::with_enclosing(Rc::clone(
+            &self.builtins,
+        ))));
+        let module_dir = canonical
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let previous_environment = mem::replace(&mut self.environment, Rc::clone(&environment));
+        let previous_dir = mem::replace(&mut self.base_dir, module_dir);
+        self.importing.push(canonical.clone());
+        let result = self.interpret(&statements);
+        self.importing.pop();
+        self.base_dir = previous_dir;
+        self.environment = previous_environment;
+        result?;
+
+        let exports = statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Export(export) => Some(export.names()),
+                _ => None,
+            })
+            .flatten()
+            .map(|name| name.lexeme.clone())
+            .collect();
+        let module = Rc::new(LoxModule::new(path, environment, exports));
+        self.modules.insert(canonical, Rc::clone(&module));
+        Ok(module)
+    }
+
+    /// The value a `catch` clause binds for `error`: whatever was thrown, or an `Error`
+    /// instance carrying the `message` and `line` of a runtime error.
+    fn error_value(&self, error: RuntimeError) -> LiteralValue {
+        if let Some(value) = error.thrown {
+            return value;
+        }
+        let mut instance = LoxInstance::new(self.error_class());
+        instance.set_field("message", LiteralValue::String(error.message));
+        instance.set_field("line", LiteralValue::Number(error.line as f64));
+        LiteralValue::Instance(Rc::new(RefCell::new(instance)))
+    }
+
+    /// Record variable depths computed by the `Resolver` for code about to be interpreted.
+    pub fn resolve(&mut self, locals: HashMap<ExprId, usize>) {
+        self.locals.extend(locals);
+    }
+
     pub fn interpret(&mut self, statements: &Vec<Stmt>) -> Result<(), RuntimeError> {
         for stmt in statements {
-            stmt.accept_mut(self)?;
+            match stmt.accept_mut(self) {
+                Ok(()) => {}
+                // A top-level `return` simply ends the script.
+                Err(Unwind::Return(_) | Unwind::Break | Unwind::Continue) => return Ok(()),
+                Err(Unwind::Error(e)) => return Err(e),
+            }
         }
         Ok(())
     }
 
-    pub fn define(&mut self, name: &String, value: LiteralValue) {
-        self.environment.values.insert(name.clone(), value);
+    /// Run `statements` with `environment` as the current scope, restoring the previous
+    /// scope afterwards even if execution fails.
+    pub fn execute_block(
+        &mut self,
+        statements: &[Stmt],
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<(), Unwind> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = statements.iter().try_for_each(|stmt| stmt.accept_mut(self));
+        self.environment = previous;
+        result
     }
 
-    pub fn get(&self, line: usize, name: &String) -> Result<LiteralValue, RuntimeError> {
-        self.environment
-            .get(name)
-            .ok_or_else(|| RuntimeError::undefined_variable(name.clone(), line))
+    /// Bind `name` in the current scope. A constant can't share its name with any other
+    /// declaration in the same scope.
+    fn declare(
+        &mut self,
+        name: &Token,
+        value: LiteralValue,
+        constant: bool,
+    ) -> Result<(), RuntimeError> {
+        let mut environment = self.environment.borrow_mut();
+        if environment.conflicts_with_constant(&name.lexeme, constant) {
+            return Err(RuntimeError::redeclared_constant(&name.lexeme, name.line));
+        }
+        if constant {
+            environment.define_const(&name.lexeme, value);
+        } else {
+            environment.define(&name.lexeme, value);
+        }
+        Ok(())
+    }
+
+    fn look_up_variable(&self, id: ExprId, name: &Token) -> Result<LiteralValue, RuntimeError> {
+        let value = match self.locals.get(&id) {
+            Some(distance) => self.environment.borrow().get_at(*distance, &name.lexeme),
+            None => self.module_globals().borrow().get(&name.lexeme),
+        };
+        value.ok_or_else(|| RuntimeError::undefined_variable(name.lexeme.clone(), name.line))
+    }
+
+    fn assign_variable(
+        &mut self,
+        id: ExprId,
+        name: &Token,
+        value: LiteralValue,
+    ) -> Result<(), RuntimeError> {
+        let assignment = match self.locals.get(&id) {
+            Some(distance) => {
+                self.environment
+                    .borrow_mut()
+                    .assign_at(*distance, &name.lexeme, value)
+            }
+            // Builtins are shared by every module, so they can be shadowed but never assigned.
+            None => self
+                .module_globals()
+                .borrow_mut()
+                .assign_at(0, &name.lexeme, value),
+        };
+        match assignment {
+            Assignment::Assigned => Ok(()),
+            Assignment::Undefined if self.builtins.borrow().get(&name.lexeme).is_some() => {
+                Err(RuntimeError::assign_to_builtin(&name.lexeme, name.line))
+            }
+            Assignment::Undefined => Err(RuntimeError::undefined_variable(
+                name.lexeme.clone(),
+                name.line,
+            )),
+            Assignment::Constant => Err(RuntimeError::assign_to_constant(&name.lexeme, name.line)),
+        }
     }
 }
 
 type LiteralValueResult = Result<LiteralValue, RuntimeError>;
 
-impl StmtVisitorMut<Result<(), RuntimeError>> for Interpreter {
-    fn visit_expr(&mut self, expr: &Expr) -> Result<(), RuntimeError> {
-        expr.accept_mut::<LiteralValueResult>(self).map(|_| ())
+impl StmtVisitorMut<Result<(), Unwind>> for Interpreter {
+    fn visit_expr(&mut self, expr: &Expr) -> Result<(), Unwind> {
+        expr.accept_mut::<LiteralValueResult>(self)?;
+        Ok(())
     }
 
-    fn visit_print(&mut self, expr: &Expr) -> Result<(), RuntimeError> {
+    fn visit_print(&mut self, expr: &Expr) -> Result<(), Unwind> {
         let value = expr.accept_mut::<LiteralValueResult>(self)?;
         println!("{}", value);
         Ok(())
     }
 
-    fn visit_variable(&mut self, var: &VarAssignment) -> Result<(), RuntimeError> {
+    fn visit_variable(&mut self, var: &VarAssignment) -> Result<(), Unwind> {
         if let Some(expr) = &var.initializer {
             let value = expr.accept_mut::<LiteralValueResult>(self)?;
-            let name = &var.token.lexeme;
-            self.define(name, value);
+            self.declare(&var.token, value, var.constant)?;
             return Ok(());
         }
 
-        self.define(&var.token.lexeme, LiteralValue::Nil);
+        self.declare(&var.token, LiteralValue::Nil, false)?;
+
+        Ok(())
+    }
 
+    fn visit_var_destructure(&mut self, stmt: &VarDestructure) -> Result<(), Unwind> {
+        let value = stmt.initializer.accept_mut::<LiteralValueResult>(self)?;
+        let mut bindings = Vec::new();
+        unpack(&stmt.target, value, &mut bindings)?;
+        for (_, name, value) in bindings {
+            self.declare(name, value, stmt.constant)?;
+        }
+        Ok(())
+    }
+
+    fn visit_block(&mut self, stmts: &[Stmt]) -> Result<(), Unwind> {
+        let environment = Environment::with_enclosing(Rc::clone(&self.environment));
+        self.execute_block(stmts, Rc::new(RefCell::new(environment)))
+    }
+
+    fn visit_if(&mut self, stmt: &If) -> Result<(), Unwind> {
+        let condition = stmt.condition.accept_mut::<LiteralValueResult>(self)?;
+        if condition.is_truthy() {
+            stmt.then_branch.accept_mut(self)
+        } else if let Some(else_branch) = &stmt.else_branch {
+            else_branch.accept_mut(self)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn visit_while(&mut self, stmt: &While) -> Result<(), Unwind> {
+        while stmt
+            .condition
+            .accept_mut::<LiteralValueResult>(self)?
+            .is_truthy()
+        {
+            match stmt.body.accept_mut(self) {
+                Ok(()) | Err(Unwind::Continue) => {}
+                Err(Unwind::Break) => break,
+                Err(other) => return Err(other),
+            }
+            if let Some(increment) = &stmt.increment {
+                increment.accept_mut::<LiteralValueResult>(self)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_function(&mut self, stmt: &Rc<Function>) -> Result<(), Unwind> {
+        let function = LoxFunction::new(Rc::clone(stmt), Rc::clone(&self.environment), false);
+        self.declare(&stmt.name, LiteralValue::Function(Rc::new(function)), false)?;
+        Ok(())
+    }
+
+    fn visit_return(&mut self, stmt: &Return) -> Result<(), Unwind> {
+        let value = match &stmt.value {
+            Some(value) => value.accept_mut::<LiteralValueResult>(self)?,
+            None => LiteralValue::Nil,
+        };
+        Err(Unwind::Return(value))
+    }
+
+    fn visit_break(&mut self, _keyword: &Token) -> Result<(), Unwind> {
+        Err(Unwind::Break)
+    }
+
+    fn visit_continue(&mut self, _keyword: &Token) -> Result<(), Unwind> {
+        Err(Unwind::Continue)
+    }
+
+    fn visit_match(&mut self, stmt: &Match) -> Result<(), Unwind> {
+        let value = stmt.value.accept_mut::<LiteralValueResult>(self)?;
+        for arm in &stmt.arms {
+            if !arm.patterns.iter().any(|p| pattern_matches(p, &value)) {
+                continue;
+            }
+            if let Some(guard) = &arm.guard
+                && !guard.accept_mut::<LiteralValueResult>(self)?.is_truthy()
+            {
+                continue;
+            }
+            return arm.body.accept_mut(self);
+        }
+        Ok(())
+    }
+
+    fn visit_throw(&mut self, stmt: &Throw) -> Result<(), Unwind> {
+        let value = stmt.value.accept_mut::<LiteralValueResult>(self)?;
+        Err(RuntimeError::thrown(value, stmt.keyword.line).into())
+    }
+
+    fn visit_try(&mut self, stmt: &Try) -> Result<(), Unwind> {
+        let result = match (self.visit_block(&stmt.body), &stmt.catch) {
+            (Err(Unwind::Error(error)), Some(catch)) => {
+                let mut environment = Environment::with_enclosing(Rc::clone(&self.environment));
+                environment.define(&catch.name.lexeme, self.error_value(error));
+                self.execute_block(&catch.body, Rc::new(RefCell::new(environment)))
+            }
+            (result, _) => result,
+        };
+        // `finally` always runs; if it unwinds itself, that replaces the pending outcome.
+        if let Some(finally) = &stmt.finally {
+            self.visit_block(finally)?;
+        }
+        result
+    }
+
+    fn visit_import(&mut self, stmt: &Import) -> Result<(), Unwind> {
+        let module = self.import_module(&stmt.path, stmt.keyword.line)?;
+        self.declare(&stmt.name, LiteralValue::Module(module), false)?;
+        Ok(())
+    }
+
+    fn visit_export(&mut self, stmt: &Export) -> Result<(), Unwind> {
+        stmt.declaration.accept_mut(self)
+    }
+
+    fn visit_class(&mut self, stmt: &Class) -> Result<(), Unwind> {
+        let superclass = match &stmt.superclass {
+            Some(expr) => match expr.accept_mut::<LiteralValueResult>(self)? {
+                LiteralValue::Class(class) => Some(class),
+                other => {
+                    return Err(RuntimeError::superclass_not_a_class(&other, stmt.name.line).into());
+                }
+            },
+            None => None,
+        };
+
+        self.declare(&stmt.name, LiteralValue::Nil, false)?;
+
+        // Methods of a subclass close over an extra scope that binds `super`.
+        let closure = match &superclass {
+            Some(superclass) => {
+                let mut environment = Environment::with_enclosing(Rc::clone(&self.environment));
+                environment.define("super", LiteralValue::Class(Rc::clone(superclass)));
+                Rc::new(RefCell::new(environment))
+            }
+            None => Rc::clone(&self.environment),
+        };
+
+        let methods = stmt
+            .methods
+            .iter()
+            .map(|method| {
+                let is_initializer = method.name.lexeme == "init";
+                let function =
+                    LoxFunction::new(Rc::clone(method), Rc::clone(&closure), is_initializer);
+                (method.name.lexeme.clone(), Rc::new(function))
+            })
+            .collect();
+
+        let class = LoxClass::new(&stmt.name.lexeme, superclass, methods);
+        self.environment
+            .borrow_mut()
+            .assign(&stmt.name.lexeme, LiteralValue::Class(Rc::new(class)));
         Ok(())
     }
 }
@@ -134,21 +941,234 @@ impl ExprVisitorMut<Result<LiteralValue, RuntimeError>> for Interpreter {
         evaluate_binary_expr(left, right, &expr.operator)
     }
 
-    fn visit_variable(&mut self, token: &Token) -> Result<LiteralValue, RuntimeError> {
-        self.get(token.line, &token.lexeme)
+    /// Short-circuits and yields the operand that decided the result, not a coerced boolean.
+    fn visit_logical(&mut self, expr: &Logical) -> Result<LiteralValue, RuntimeError> {
+        let left = expr.left.accept_mut(self)?;
+        let decided = match expr.operator.typ {
+            TokenType::Or => left.is_truthy(),
+            _ => !left.is_truthy(),
+        };
+        if decided {
+            Ok(left)
+        } else {
+            expr.right.accept_mut(self)
+        }
+    }
+
+    fn visit_conditional(&mut self, expr: &Conditional) -> Result<LiteralValue, RuntimeError> {
+        if expr.condition.accept_mut(self)?.is_truthy() {
+            expr.then_branch.accept_mut(self)
+        } else {
+            expr.else_branch.accept_mut(self)
+        }
+    }
+
+    fn visit_lambda(&mut self, function: &Rc<Function>) -> Result<LiteralValue, RuntimeError> {
+        let function = LoxFunction::new(Rc::clone(function), Rc::clone(&self.environment), false);
+        Ok(LiteralValue::Function(Rc::new(function)))
+    }
+
+    fn visit_call(&mut self, expr: &Call) -> Result<LiteralValue, RuntimeError> {
+        let callee = expr.callee.accept_mut(self)?;
+        let arguments = expr
+            .arguments
+            .iter()
+            .map(|argument| argument.accept_mut(self))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let function: &dyn Callable = match &callee {
+            LiteralValue::Function(function) => function.as_ref(),
+            LiteralValue::NativeFunction(function) => function.as_ref(),
+            LiteralValue::Class(class) => class,
+            _ => return Err(RuntimeError::not_callable(&callee, expr.paren.line)),
+        };
+        if arguments.len() != function.arity() {
+            return Err(RuntimeError::arity_mismatch(
+                function.arity(),
+                arguments.len(),
+                expr.paren.line,
+            ));
+        }
+        function.call(self, arguments, expr.paren.line)
+    }
+
+    fn visit_variable(&mut self, id: ExprId, token: &Token) -> Result<LiteralValue, RuntimeError> {
+        self.look_up_variable(id, token)
     }
 
     fn visit_assign(
         &mut self,
+        id: ExprId,
         token: &Token,
         value: &Box<Expr>,
     ) -> Result<LiteralValue, RuntimeError> {
         let val = value.accept_mut(self)?;
-        self.define(&token.lexeme, val.clone());
+        self.assign_variable(id, token, val.clone())?;
 
         Ok(val)
     }
 
+    fn visit_get(&mut self, expr: &Get) -> Result<LiteralValue, RuntimeError> {
+        match expr.object.accept_mut(self)? {
+            LiteralValue::Instance(instance) => LoxInstance::get(&instance, &expr.name),
+            LiteralValue::Module(module) => module.get(&expr.name),
+            other => Err(RuntimeError::not_an_instance(
+                &other,
+                "properties",
+                expr.name.line,
+            )),
+        }
+    }
+
+    fn visit_set(&mut self, expr: &Set) -> Result<LiteralValue, RuntimeError> {
+        let instance = match expr.object.accept_mut(self)? {
+            LiteralValue::Instance(instance) => instance,
+            other => {
+                return Err(RuntimeError::not_an_instance(
+                    &other,
+                    "fields",
+                    expr.name.line,
+                ));
+            }
+        };
+        let value = expr.value.accept_mut(self)?;
+        instance.borrow_mut().set(&expr.name, value.clone());
+        Ok(value)
+    }
+
+    fn visit_list(&mut self, expr: &List) -> Result<LiteralValue, RuntimeError> {
+        let elements = expr
+            .elements
+            .iter()
+            .map(|element| element.accept_mut(self))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(LiteralValue::List(Rc::new(RefCell::new(elements))))
+    }
+
+    fn visit_tuple(&mut self, expr: &Tuple) -> Result<LiteralValue, RuntimeError> {
+        let elements = expr
+            .elements
+            .iter()
+            .map(|element| element.accept_mut(self))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(LiteralValue::Tuple(Rc::new(elements)))
+    }
+
+    fn visit_destructure(&mut self, expr: &Destructure) -> Result<LiteralValue, RuntimeError> {
+        // The whole right-hand side is evaluated first, so `(a, b) = (b, a)` swaps.
+        let value = expr.value.accept_mut(self)?;
+        let mut bindings = Vec::new();
+        unpack(&expr.target, value.clone(), &mut bindings)?;
+        for (id, name, element) in bindings {
+            self.assign_variable(id, name, element)?;
+        }
+        Ok(value)
+    }
+
+    fn visit_index(&mut self, expr: &Index) -> Result<LiteralValue, RuntimeError> {
+        let object = expr.object.accept_mut(self)?;
+        let index = expr.index.accept_mut(self)?;
+        index_get(&object, &index, expr.bracket.line)
+    }
+
+    fn visit_index_set(&mut self, expr: &IndexSet) -> Result<LiteralValue, RuntimeError> {
+        let object = expr.object.accept_mut(self)?;
+        if !matches!(object, LiteralValue::List(_) | LiteralValue::Map(_)) {
+            return Err(RuntimeError::not_indexable(&object, expr.bracket.line));
+        }
+        let index = expr.index.accept_mut(self)?;
+        let value = expr.value.accept_mut(self)?;
+        index_set(&object, index, value.clone(), expr.bracket.line)?;
+        Ok(value)
+    }
+
+    fn visit_compound(&mut self, expr: &Compound) -> Result<LiteralValue, RuntimeError> {
+        let line = expr.operator.line;
+        let update = |interpreter: &mut Self, old: &LiteralValue| {
+            let value = expr.value.accept_mut(interpreter)?;
+            evaluate_binary_expr(old.clone(), value, &expr.operator)
+        };
+        let (old, new) = match expr.target.as_ref() {
+            Expr::Variable { id, token } => {
+                let old = self.look_up_variable(*id, token)?;
+                let new = update(self, &old)?;
+                self.assign_variable(*id, token, new.clone())?;
+                (old, new)
+            }
+            Expr::Get(get) => {
+                let instance = match get.object.accept_mut(self)? {
+                    LiteralValue::Instance(instance) => instance,
+                    other => return Err(RuntimeError::not_an_instance(&other, "fields", line)),
+                };
+                let old = LoxInstance::get(&instance, &get.name)?;
+                let new = update(self, &old)?;
+                instance.borrow_mut().set(&get.name, new.clone());
+                (old, new)
+            }
+            Expr::Index(index) => {
+                let object = index.object.accept_mut(self)?;
+                let key = index.index.accept_mut(self)?;
+                let old = index_get(&object, &key, index.bracket.line)?;
+                let new = update(self, &old)?;
+                index_set(&object, key, new.clone(), index.bracket.line)?;
+                (old, new)
+            }
+            _ => unreachable!("the parser only builds compound assignments to valid targets"),
+        };
+        Ok(if expr.postfix { old } else { new })
+    }
+
+    fn visit_map(&mut self, expr: &Map) -> Result<LiteralValue, RuntimeError> {
+        let mut map = LoxMap::new();
+        for (key, value) in &expr.entries {
+            let key = key.accept_mut(self)?;
+            let hashed = MapKey::new(&key, expr.brace.line)?;
+            let value = value.accept_mut(self)?;
+            map.insert(hashed, key, value);
+        }
+        Ok(LiteralValue::Map(Rc::new(RefCell::new(map))))
+    }
+
+    /// Every part is shown the way `print` shows it, except that strings are never quoted,
+    /// however deeply nested: `"${"a"} ${["b", {"c": 1}]}"` is `a [b, {c: 1}]`.
+    fn visit_interpolation(&mut self, expr: &Interpolation) -> Result<LiteralValue, RuntimeError> {
+        let mut out = String::new();
+        for part in &expr.parts {
+            out.push_str(&format!("{:#}", part.accept_mut(self)?));
+        }
+        Ok(LiteralValue::String(out))
+    }
+
+    fn visit_this(&mut self, id: ExprId, keyword: &Token) -> Result<LiteralValue, RuntimeError> {
+        self.look_up_variable(id, keyword)
+    }
+
+    fn visit_super(&mut self, expr: &Super) -> Result<LiteralValue, RuntimeError> {
+        let line = expr.keyword.line;
+        let distance = *self
+            .locals
+            .get(&expr.id)
+            .ok_or_else(|| RuntimeError::unresolved("super", line))?;
+        let superclass = match self.environment.borrow().get_at(distance, "super") {
+            Some(LiteralValue::Class(class)) => class,
+            Some(other) => return Err(RuntimeError::superclass_not_a_class(&other, line)),
+            None => return Err(RuntimeError::unresolved("super", line)),
+        };
+        // `this` is always bound one scope inside the scope that binds `super`.
+        let object = distance
+            .checked_sub(1)
+            .and_then(|distance| self.environment.borrow().get_at(distance, "this"))
+            .ok_or_else(|| RuntimeError::unresolved("this", line))?;
+        match superclass.find_method(&expr.method.lexeme) {
+            Some(method) => Ok(LiteralValue::Function(Rc::new(method.bind(object)))),
+            None => Err(RuntimeError::undefined_property(
+                &expr.method.lexeme,
+                &superclass.to_string(),
+                expr.method.line,
+            )),
+        }
+    }
+
     fn visit_unary(&mut self, expr: &Unary) -> Result<LiteralValue, RuntimeError> {
         let operator = expr.operator.typ;
         let right = expr.right.accept_mut(self)?;
@@ -190,9 +1210,11 @@ fn evaluate_binary_expr(
 
     let eq = |f: fn(&LiteralValue, &LiteralValue) -> bool| -> Result<LiteralValue, RuntimeError> {
         match (&left, &right) {
-            (LiteralValue::Number(_), LiteralValue::Number(_))
-            | (LiteralValue::String(_), LiteralValue::String(_))
-            | (LiteralValue::Boolean(_), LiteralValue::Boolean(_)) => {
+            (LiteralValue::Nil, _) | (_, LiteralValue::Nil) => {
+                Ok(LiteralValue::Boolean(f(&left, &right)))
+            }
+            // Values of the same kind are comparable; callables and instances by identity.
+            _ if std::mem::discriminant(&left) == std::mem::discriminant(&right) => {
                 Ok(LiteralValue::Boolean(f(&left, &right)))
             }
             _ => Err(RuntimeError::invalid_operands(
@@ -232,6 +1254,20 @@ fn evaluate_binary_expr(
         TokenType::Minus => num(|l, r| l - r),
         TokenType::Star => num(|l, r| l * r),
         TokenType::Slash => num(|l, r| l / r),
+        TokenType::StarStar => num(f64::powf),
+        // `%` takes the sign of the divisor so that `a == b * (a div b) + a % b`.
+        TokenType::Percent | TokenType::Div => match (&left, &right) {
+            (LiteralValue::Number(_), LiteralValue::Number(r)) if *r == 0.0 => {
+                let operation = if operator_type == TokenType::Percent {
+                    "Modulo"
+                } else {
+                    "Integer division"
+                };
+                Err(RuntimeError::division_by_zero(operation, op.line))
+            }
+            _ if operator_type == TokenType::Percent => num(|l, r| l - r * (l / r).floor()),
+            _ => num(|l, r| (l / r).floor()),
+        },
         TokenType::EqualEqual => eq(|l, r| l == r),
         TokenType::BangEqual => eq(|l, r| l != r),
         TokenType::Greater | TokenType::GreaterEqual | TokenType::Less | TokenType::LessEqual => {
@@ -257,3 +1293,923 @@ fn evaluate_binary_expr(
         _ => Err(RuntimeError::invalid_operator(operator_type, op.clone())),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::resolver::Resolver;
+    use crate::scanner::Scanner;
+
+    /// Scan, parse and resolve `source`, ready to hand to an interpreter.
+    fn compile(source: &str) -> (Vec<Stmt>, HashMap<ExprId, usize>) {
+        let mut scanner = Scanner::new(source.to_string());
+        let tokens = scanner.scan_tokens();
+        assert!(!scanner.has_error(), "Scanner reported an error.");
+        let stmts = Parser::new(tokens)
+            .parse()
+            .expect("Parser returned an error");
+        let locals = Resolver::new()
+            .resolve(&stmts)
+            .unwrap_or_else(|e| panic!("{}", e));
+        (stmts, locals)
+    }
+
+    /// Scan, parse and run `source` in a fresh interpreter, returning it for inspection.
+    fn run(source: &str) -> Interpreter {
+        let (stmts, locals) = compile(source);
+        let mut interp = Interpreter::new();
+        interp.resolve(locals);
+        if let Err(e) = interp.interpret(&stmts) {
+            panic!("[line {}] Error: {}", e.line, e.message);
+        }
+        interp
+    }
+
+    /// Run `source` expecting it to fail at runtime, returning the error.
+    fn run_err(source: &str) -> RuntimeError {
+        let (stmts, locals) = compile(source);
+        let mut interp = Interpreter::new();
+        interp.resolve(locals);
+        match interp.interpret(&stmts) {
+            Ok(()) => panic!("Expected a runtime error."),
+            Err(e) => e,
+        }
+    }
+
+    fn global(interp: &Interpreter, name: &str) -> LiteralValue {
+        interp
+            .environment
+            .borrow()
+            .get(name)
+            .unwrap_or_else(|| panic!("Undefined global: {}", name))
+    }
+
+    #[test]
+    fn block_shadows_and_restores_outer_variable() {
+        let interp = run("var a = 1; var b = 0; { var a = 2; b = a; }");
+        assert_eq!(global(&interp, "a"), LiteralValue::Number(1.0));
+        assert_eq!(global(&interp, "b"), LiteralValue::Number(2.0));
+    }
+
+    #[test]
+    fn block_locals_do_not_leak() {
+        let interp = run("{ var temp = 1; }");
+        assert!(interp.environment.borrow().get("temp").is_none());
+    }
+
+    #[test]
+    fn assignment_in_block_updates_enclosing_scope() {
+        let interp = run("var a = 1; { { a = 3; } }");
+        assert_eq!(global(&interp, "a"), LiteralValue::Number(3.0));
+    }
+
+    #[test]
+    fn if_runs_then_branch_when_truthy() {
+        let interp = run("var a = 0; if (1 < 2) a = 1; else a = 2;");
+        assert_eq!(global(&interp, "a"), LiteralValue::Number(1.0));
+    }
+
+    #[test]
+    fn if_runs_else_branch_when_falsey() {
+        let interp = run("var a = 0; if (nil) a = 1; else a = 2;");
+        assert_eq!(global(&interp, "a"), LiteralValue::Number(2.0));
+    }
+
+    #[test]
+    fn while_loop_runs_until_condition_is_falsey() {
+        let interp = run("var i = 0; var sum = 0; while (i < 4) { sum = sum + i; i = i + 1; }");
+        assert_eq!(global(&interp, "sum"), LiteralValue::Number(6.0));
+    }
+
+    #[test]
+    fn for_loop_sums_a_series_without_leaking_its_variable() {
+        let interp = run("var sum = 0; for (var i = 1; i <= 10; i = i + 1) sum = sum + i;");
+        assert_eq!(global(&interp, "sum"), LiteralValue::Number(55.0));
+        assert!(interp.environment.borrow().get("i").is_none());
+    }
+
+    #[test]
+    fn logical_operators_return_the_deciding_operand() {
+        let interp = run("var a = nil or \"yes\"; var b = 0 and 2; var c = false and missing;");
+        assert_eq!(
+            global(&interp, "a"),
+            LiteralValue::String("yes".to_string())
+        );
+        assert_eq!(global(&interp, "b"), LiteralValue::Number(2.0));
+        assert_eq!(global(&interp, "c"), LiteralValue::Boolean(false));
+    }
+
+    #[test]
+    fn logical_guard_skips_right_operand() {
+        let interp = run("var x = nil; var ok = x != nil and x > 3;");
+        assert_eq!(global(&interp, "ok"), LiteralValue::Boolean(false));
+    }
+
+    #[test]
+    fn assignment_to_undefined_variable_is_an_error() {
+        run_err("missing = 1;");
+    }
+
+    #[test]
+    fn closures_bind_to_the_variable_in_scope_at_declaration() {
+        let interp = run("var first; var second; var a = \"global\";
+             {
+               fun showA() { return a; }
+               first = showA();
+               var a = \"block\";
+               second = showA();
+             }");
+        assert_eq!(
+            global(&interp, "first"),
+            LiteralValue::String("global".to_string())
+        );
+        assert_eq!(
+            global(&interp, "second"),
+            LiteralValue::String("global".to_string())
+        );
+    }
+
+    #[test]
+    fn break_exits_the_nearest_loop() {
+        let interp = run("var count = 0;
+             while (true) {
+               for (var i = 0; i < 10; i = i + 1) { if (i == 2) break; count = count + 1; }
+               break;
+             }");
+        assert_eq!(global(&interp, "count"), LiteralValue::Number(2.0));
+    }
+
+    #[test]
+    fn continue_in_for_loop_still_runs_increment() {
+        let interp = run("var sum = 0;
+             for (var i = 0; i < 5; i = i + 1) { if (i == 2) continue; sum = sum + i; }");
+        assert_eq!(global(&interp, "sum"), LiteralValue::Number(8.0));
+    }
+
+    #[test]
+    fn functions_return_values_and_recurse() {
+        let interp = run(
+            "fun fib(n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); }
+             var result = fib(10);",
+        );
+        assert_eq!(global(&interp, "result"), LiteralValue::Number(55.0));
+    }
+
+    #[test]
+    fn function_without_return_yields_nil() {
+        let interp = run("fun noop() {} var result = noop();");
+        assert_eq!(global(&interp, "result"), LiteralValue::Nil);
+    }
+
+    #[test]
+    fn closures_capture_their_defining_environment() {
+        let interp = run("fun makeCounter() {
+               var count = 0;
+               fun increment() { count = count + 1; return count; }
+               return increment;
+             }
+             var counter = makeCounter();
+             counter();
+             var result = counter();");
+        assert_eq!(global(&interp, "result"), LiteralValue::Number(2.0));
+    }
+
+    #[test]
+    fn arity_mismatch_reports_call_line() {
+        let e = run_err("fun f(a, b) {}\nf(1);");
+        assert_eq!(e.line, 2);
+        assert_eq!(e.message, "Expected 2 arguments but got 1.");
+    }
+
+    #[test]
+    fn clock_is_registered_by_default() {
+        let interp = run("var now = clock();");
+        assert!(matches!(global(&interp, "now"), LiteralValue::Number(n) if n > 0.0));
+    }
+
+    #[test]
+    fn registered_natives_receive_arguments_and_report_call_line() {
+        let (stmts, locals) = compile("var a = twice(21);\ntwice(nil);");
+        let mut interp = Interpreter::new();
+        interp.resolve(locals);
+        interp.register_native("twice", 1, |args| match &args[0] {
+            LiteralValue::Number(n) => Ok(LiteralValue::Number(n * 2.0)),
+            _ => Err(RuntimeError::native("twice expects a number.")),
+        });
+
+        let Err(e) = interp.interpret(&stmts) else {
+            panic!("Expected a runtime error.");
+        };
+        assert_eq!(global(&interp, "a"), LiteralValue::Number(42.0));
+        assert_eq!(e.line, 2);
+        assert_eq!(e.message, "twice expects a number.");
+    }
+
+    #[test]
+    fn instances_hold_fields_and_call_methods() {
+        let interp = run("class Point {
+               init(x, y) { this.x = x; this.y = y; }
+               sum() { return this.x + this.y; }
+             }
+             var p = Point(1, 2);
+             p.x = 10;
+             var result = p.sum();");
+        assert_eq!(global(&interp, "result"), LiteralValue::Number(12.0));
+    }
+
+    #[test]
+    fn bound_methods_remember_their_instance() {
+        let interp = run(
+            "class Box { init(v) { this.v = v; } get() { return this.v; } }
+             var getter = Box(7).get;
+             var result = getter();",
+        );
+        assert_eq!(global(&interp, "result"), LiteralValue::Number(7.0));
+    }
+
+    #[test]
+    fn initializer_returns_this_even_when_called_directly() {
+        let interp = run("class A { init() { this.n = 1; return; } }
+             var a = A();
+             var again = a.init();
+             var same = a == again;");
+        assert_eq!(global(&interp, "same"), LiteralValue::Boolean(true));
+    }
+
+    #[test]
+    fn subclasses_inherit_and_call_super_methods() {
+        let interp = run(
+            "class A { name() { return \"A\"; } greet() { return \"hi \" + this.name(); } }
+             class B < A { name() { return \"B\" + super.name(); } }
+             var result = B().greet();",
+        );
+        assert_eq!(
+            global(&interp, "result"),
+            LiteralValue::String("hi BA".to_string())
+        );
+    }
+
+    #[test]
+    fn undefined_property_is_a_runtime_error() {
+        let e = run_err("class A {}\nA().missing;");
+        assert_eq!(e.line, 2);
+        assert_eq!(e.message, "Undefined property 'missing' on A instance.");
+    }
+
+    #[test]
+    fn property_access_on_non_instance_is_a_runtime_error() {
+        let e = run_err("var x = 1; x.y = 2;");
+        assert_eq!(e.message, "Only instances have fields, got 1.");
+        let e = run_err("\"s\".length;");
+        assert_eq!(e.message, "Only instances have properties, got \"s\".");
+    }
+
+    #[test]
+    fn inheriting_from_a_non_class_is_a_runtime_error() {
+        let e = run_err("var NotAClass = 1; class B < NotAClass {}");
+        assert_eq!(e.message, "Superclass must be a class, got 1.");
+    }
+
+    #[test]
+    fn calling_a_non_callable_is_an_error() {
+        let e = run_err("var x = 1; x();");
+        assert_eq!(e.message, "Can only call functions and classes, got 1.");
+    }
+
+    #[test]
+    fn lists_support_indexing_and_mutation() {
+        let interp = run("var xs = [1, 2, 3];
+             xs[0] = xs[2] + 1;
+             push(xs, 5);
+             var last = pop(xs);
+             var first = xs[0];
+             var size = len(xs);");
+        assert_eq!(global(&interp, "first"), LiteralValue::Number(4.0));
+        assert_eq!(global(&interp, "last"), LiteralValue::Number(5.0));
+        assert_eq!(global(&interp, "size"), LiteralValue::Number(3.0));
+    }
+
+    #[test]
+    fn lists_are_shared_by_reference() {
+        let interp = run("var a = []; var b = a; push(b, 1); var n = len(a); var same = a == b;");
+        assert_eq!(global(&interp, "n"), LiteralValue::Number(1.0));
+        assert_eq!(global(&interp, "same"), LiteralValue::Boolean(true));
+        assert_eq!(global(&interp, "a").to_string(), "[1]");
+    }
+
+    #[test]
+    fn bad_list_indices_are_runtime_errors() {
+        let e = run_err("var xs = [1, 2];\nxs[2];");
+        assert_eq!(e.line, 2);
+        assert_eq!(
+            e.message,
+            "List index 2 is out of range for a list of length 2."
+        );
+        let e = run_err("[1][1e20];");
+        assert_eq!(
+            e.message,
+            "List index 100000000000000000000 is out of range for a list of length 1."
+        );
+        let e = run_err("[1][0.5];");
+        assert_eq!(
+            e.message,
+            "List index must be a non-negative integer, got 0.5."
+        );
+        let e = run_err("[1][-1] = 3;");
+        assert_eq!(
+            e.message,
+            "List index must be a non-negative integer, got -1."
+        );
+        let e = run_err("var x = 1; x[0];");
+        assert_eq!(e.message, "Only lists and maps can be indexed, got 1.");
+    }
+
+    #[test]
+    fn popping_an_empty_list_is_a_runtime_error() {
+        let e = run_err("\npop([]);");
+        assert_eq!(e.line, 2);
+        assert_eq!(e.message, "Can't pop from an empty list.");
+    }
+
+    #[test]
+    fn maps_support_literals_lookup_and_insertion() {
+        let interp = run("var m = {\"a\": 1, 2: \"two\"};
+             m[\"b\"] = m[\"a\"] + 1;
+             m[\"a\"] = 10;
+             var a = m[\"a\"];
+             var two = m[2];
+             var size = len(m);
+             var found = has(m, \"b\");
+             var missing = has(m, nil);");
+        assert_eq!(global(&interp, "a"), LiteralValue::Number(10.0));
+        assert_eq!(
+            global(&interp, "two"),
+            LiteralValue::String("two".to_string())
+        );
+        assert_eq!(global(&interp, "size"), LiteralValue::Number(3.0));
+        assert_eq!(global(&interp, "found"), LiteralValue::Boolean(true));
+        assert_eq!(global(&interp, "missing"), LiteralValue::Boolean(false));
+        assert_eq!(
+            global(&interp, "m").to_string(),
+            "{\"a\": 10, 2: \"two\", \"b\": 2}"
+        );
+    }
+
+    #[test]
+    fn map_keys_and_values_keep_insertion_order() {
+        let interp = run("var m = {\"z\": 1, \"y\": 2}; var k = keys(m); var v = values(m);");
+        assert_eq!(global(&interp, "k").to_string(), "[\"z\", \"y\"]");
+        assert_eq!(global(&interp, "v").to_string(), "[1, 2]");
+    }
+
+    #[test]
+    fn map_errors_are_runtime_errors() {
+        let e = run_err("var m = {};\nm[\"nope\"];");
+        assert_eq!(e.line, 2);
+        assert_eq!(e.message, "Key \"nope\" not found in map.");
+        let e = run_err("var m = {[1]: 2};");
+        assert_eq!(
+            e.message,
+            "Map keys must be numbers, strings, booleans or nil, got [1]."
+        );
+        let e = run_err("\nhas({}, {});");
+        assert_eq!(e.line, 2);
+        assert_eq!(
+            e.message,
+            "Map keys must be numbers, strings, booleans or nil, got {}."
+        );
+        let e = run_err("var m = {};\nm[0 / 0] = 1;");
+        assert_eq!(e.line, 2);
+        assert_eq!(
+            e.message,
+            "Map keys must be numbers, strings, booleans or nil, got NaN."
+        );
+    }
+
+    #[test]
+    fn interpolates_expressions_into_strings() {
+        let interp = run("var name = \"Ada\"; var age = 36;
+             var greeting = \"Hello ${name}, you are ${age + 1}\";
+             var nested = \"${ \"<${ [1, {\"a\": true}] }>\" }\";
+             var top = \"[${\"quoted?\"}]\"; var tuple = \"${(\"x\", [\"y\"])}\";");
+        assert_eq!(
+            global(&interp, "greeting"),
+            LiteralValue::String("Hello Ada, you are 37".to_string())
+        );
+        assert_eq!(
+            global(&interp, "nested"),
+            LiteralValue::String("<[1, {a: true}]>".to_string())
+        );
+        assert_eq!(
+            global(&interp, "top"),
+            LiteralValue::String("[quoted?]".to_string())
+        );
+        assert_eq!(
+            global(&interp, "tuple"),
+            LiteralValue::String("(x, [y])".to_string())
+        );
+    }
+
+    #[test]
+    fn evaluates_modulo_power_and_floor_division() {
+        let interp = run(
+            "var a = 7 % 3; var b = -7 % 3; var c = 7 div 2; var d = -7 div 2;
+             var e = 2 ** 10; var f = -2 ** 2; var g = 2 ** 3 ** 2; var h = 7.5 % 2;",
+        );
+        assert_eq!(global(&interp, "a"), LiteralValue::Number(1.0));
+        assert_eq!(global(&interp, "b"), LiteralValue::Number(2.0));
+        assert_eq!(global(&interp, "c"), LiteralValue::Number(3.0));
+        assert_eq!(global(&interp, "d"), LiteralValue::Number(-4.0));
+        assert_eq!(global(&interp, "e"), LiteralValue::Number(1024.0));
+        assert_eq!(global(&interp, "f"), LiteralValue::Number(-4.0));
+        assert_eq!(global(&interp, "g"), LiteralValue::Number(512.0));
+        assert_eq!(global(&interp, "h"), LiteralValue::Number(1.5));
+    }
+
+    #[test]
+    fn modulo_and_floor_division_by_zero_are_runtime_errors() {
+        let e = run_err("var x = 0;\nprint 5 % x;");
+        assert_eq!(e.line, 2);
+        assert_eq!(e.message, "Modulo by zero.");
+        let e = run_err("print 5 div 0;");
+        assert_eq!(e.message, "Integer division by zero.");
+        let e = run_err("print \"a\" % 2;");
+        assert!(e.message.starts_with("Invalid operands"));
+    }
+
+    #[test]
+    fn conditional_evaluates_only_the_chosen_branch() {
+        let interp = run("var hits = 0;
+             fun hit(v) { hits = hits + 1; return v; }
+             var a = true ? hit(1) : hit(2);
+             var b = nil ? hit(3) : false ? hit(4) : hit(5);");
+        assert_eq!(global(&interp, "a"), LiteralValue::Number(1.0));
+        assert_eq!(global(&interp, "b"), LiteralValue::Number(5.0));
+        assert_eq!(global(&interp, "hits"), LiteralValue::Number(2.0));
+    }
+
+    #[test]
+    fn compound_assignment_updates_variables_fields_and_elements() {
+        let interp = run("var x = 10; x += 5; x -= 1; x *= 2; x /= 4;
+             var s = \"a\"; s += \"b\";
+             var xs = [1, 2]; xs[1] *= 10;
+             var m = {\"k\": 1}; m[\"k\"] -= 3;
+             var r = (x += 1);");
+        assert_eq!(global(&interp, "x"), LiteralValue::Number(8.0));
+        assert_eq!(global(&interp, "r"), LiteralValue::Number(8.0));
+        assert_eq!(global(&interp, "s"), LiteralValue::String("ab".to_string()));
+        assert_eq!(global(&interp, "xs").to_string(), "[1, 20]");
+        assert_eq!(global(&interp, "m").to_string(), "{\"k\": -2}");
+        let interp = run("class P {} var p = P(); p.n = 1; p.n += 2; var n = p.n;");
+        assert_eq!(global(&interp, "n"), LiteralValue::Number(3.0));
+    }
+
+    #[test]
+    fn increments_return_old_or_new_value() {
+        let interp = run(
+            "var i = 0; var a = i++; var b = ++i; var c = i--; var d = --i;
+             fun f() { var j = 5; j++; return j; } var e = f();",
+        );
+        assert_eq!(global(&interp, "a"), LiteralValue::Number(0.0));
+        assert_eq!(global(&interp, "b"), LiteralValue::Number(2.0));
+        assert_eq!(global(&interp, "c"), LiteralValue::Number(2.0));
+        assert_eq!(global(&interp, "d"), LiteralValue::Number(0.0));
+        assert_eq!(global(&interp, "i"), LiteralValue::Number(0.0));
+        assert_eq!(global(&interp, "e"), LiteralValue::Number(6.0));
+
+        let interp = run("var x = 2; var p = ++x ** 2; var n = -x++;");
+        assert_eq!(global(&interp, "p"), LiteralValue::Number(9.0));
+        assert_eq!(global(&interp, "n"), LiteralValue::Number(-3.0));
+        assert_eq!(global(&interp, "x"), LiteralValue::Number(4.0));
+    }
+
+    #[test]
+    fn compound_target_is_evaluated_once() {
+        let interp = run("var calls = 0; var xs = [0, 0];
+             fun pick() { calls++; return xs; }
+             pick()[1] += 5; pick()[0]++;");
+        assert_eq!(global(&interp, "calls"), LiteralValue::Number(2.0));
+        assert_eq!(global(&interp, "xs").to_string(), "[1, 5]");
+    }
+
+    #[test]
+    fn compound_assignment_checks_operand_types() {
+        let e = run_err("var s = \"a\";\ns += 1;");
+        assert_eq!(e.line, 2);
+        assert!(e.message.starts_with("Invalid operands"), "{}", e.message);
+        let e = run_err("var b = true; b++;");
+        assert!(e.message.starts_with("Invalid operands"), "{}", e.message);
+    }
+
+    #[test]
+    fn anonymous_functions_are_callable_values() {
+        let interp = run("var add = fun (a, b) { return a + b; };
+             var twice = (f, x) => f(f(x));
+             fun make_adder(n) { return (x) => x + n; }
+             var sum = add(1, 2);
+             var eleven = twice(make_adder(5), 1);
+             var immediate = fun () { return \"now\"; }();
+             var shown = \"${add}\";");
+        assert_eq!(global(&interp, "sum"), LiteralValue::Number(3.0));
+        assert_eq!(global(&interp, "eleven"), LiteralValue::Number(11.0));
+        assert_eq!(
+            global(&interp, "immediate"),
+            LiteralValue::String("now".to_string())
+        );
+        assert_eq!(
+            global(&interp, "shown"),
+            LiteralValue::String("<fn lambda>".to_string())
+        );
+    }
+
+    #[test]
+    fn anonymous_functions_check_arity() {
+        let e = run_err("var f = (a) => a;\nf(1, 2);");
+        assert_eq!(e.line, 2);
+        assert_eq!(e.message, "Expected 1 arguments but got 2.");
+    }
+
+    #[test]
+    fn match_runs_the_first_matching_arm() {
+        let interp = run("fun classify(x) {
+               var r;
+               match x {
+                 0 => r = \"zero\",
+                 1 | 2 => r = \"small\",
+                 3..10 if x != 5 => r = \"medium\",
+                 3..=10 => { r = \"five or ten\"; }
+                 \"a\" | nil => r = \"other\",
+                 _ => r = \"big\"
+               }
+               return r;
+             }
+             var results = [classify(0), classify(2), classify(4), classify(5), classify(10),
+                            classify(\"a\"), classify(nil), classify(11), classify(true)];");
+        assert_eq!(
+            global(&interp, "results").to_string(),
+            "[\"zero\", \"small\", \"medium\", \"five or ten\", \"five or ten\", \"other\", \"other\", \"big\", \"big\"]"
+        );
+    }
+
+    #[test]
+    fn match_accepts_comma_separated_expression_arms() {
+        let interp = run("var seen = [];
+             fun f(v) { match v { 1 => push(seen, \"one\"), \"a\" | \"b\" => push(seen, \"letter\"), _ => push(seen, \"other\") } }
+             f(1); f(\"b\"); f(true);");
+        assert_eq!(
+            global(&interp, "seen").to_string(),
+            "[\"one\", \"letter\", \"other\"]"
+        );
+    }
+
+    #[test]
+    fn match_without_a_matching_arm_does_nothing() {
+        let interp = run("var r = 1; match \"x\" { 1..3 => r = 2, \"y\" => r = 3 }");
+        assert_eq!(global(&interp, "r"), LiteralValue::Number(1.0));
+    }
+
+    #[test]
+    fn match_arms_can_break_out_of_loops() {
+        let interp = run("var i = 0;
+             while (true) { i++; match i { 3 => { break; } _ => { continue; } } }");
+        assert_eq!(global(&interp, "i"), LiteralValue::Number(3.0));
+    }
+
+    #[test]
+    fn catches_thrown_values() {
+        let interp = run("var caught;
+             try { throw {\"code\": 42}; caught = \"not reached\"; }
+             catch (e) { caught = e[\"code\"]; }");
+        assert_eq!(global(&interp, "caught"), LiteralValue::Number(42.0));
+    }
+
+    #[test]
+    fn runtime_errors_are_caught_with_message_and_line() {
+        let interp = run("var message; var line; var kind;
+             fun fail() {
+               return 1 + nil;
+             }
+             try { fail(); } catch (e) { message = e.message; line = e.line; kind = \"${e}\"; }");
+        assert!(matches!(
+            global(&interp, "message"),
+            LiteralValue::String(m) if m.starts_with("Invalid operands")
+        ));
+        assert_eq!(global(&interp, "line"), LiteralValue::Number(3.0));
+        assert_eq!(
+            global(&interp, "kind"),
+            LiteralValue::String("Error instance".to_string())
+        );
+    }
+
+    #[test]
+    fn finally_runs_on_every_exit_path() {
+        let interp = run("var log = [];
+             fun early() { try { return 1; } finally { push(log, \"return\"); } }
+             early();
+             try { try { undefined_thing; } finally { push(log, \"error\"); } } catch (e) {}
+             while (true) { try { break; } finally { push(log, \"break\"); } }
+             try {} catch (e) {} finally { push(log, \"normal\"); }");
+        assert_eq!(
+            global(&interp, "log").to_string(),
+            "[\"return\", \"error\", \"break\", \"normal\"]"
+        );
+    }
+
+    #[test]
+    fn uncaught_and_rethrown_errors_keep_their_message() {
+        let e = run_err("\nthrow \"boom\";");
+        assert_eq!(e.line, 2);
+        assert_eq!(e.message, "boom");
+        assert_eq!(e.thrown, Some(LiteralValue::String("boom".to_string())));
+        let e = run_err("try { missing; } catch (e) {\n  throw e;\n}");
+        assert_eq!(e.line, 2);
+        assert_eq!(e.message, "Undefined variable: missing");
+        let e = run_err("var err = Error(\"draft\"); err.message = \"custom\"; throw err;");
+        assert_eq!(e.message, "custom");
+    }
+
+    #[test]
+    fn error_class_takes_a_message() {
+        let interp = run("var message; var same; \
+             try { throw Error(\"boom\"); } catch (e) { message = e.message; same = e; }");
+        assert_eq!(
+            global(&interp, "message"),
+            LiteralValue::String("boom".to_string())
+        );
+        assert_eq!(global(&interp, "same").to_string(), "Error instance");
+        assert_eq!(
+            run_err("Error();").message,
+            "Expected 1 arguments but got 0."
+        );
+    }
+
+    /// A fresh directory holding the given module files, unique to this test run.
+    fn module_dir(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rlox-{}-{}", test, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (name, source) in files {
+            fs::write(dir.join(name), source).unwrap();
+        }
+        dir
+    }
+
+    /// Run `source` as if it were a script in `dir`.
+    fn run_in(dir: &Path, source: &str) -> Result<Interpreter, RuntimeError> {
+        let (stmts, locals) = compile(source);
+        let mut interp = Interpreter::new();
+        interp.set_base_dir(dir);
+        interp.resolve(locals);
+        interp.interpret(&stmts).map(|()| interp)
+    }
+
+    fn run_ok_in(dir: &Path, source: &str) -> Interpreter {
+        run_in(dir, source).unwrap_or_else(|e| panic!("[line {}] Error: {}", e.line, e.message))
+    }
+
+    fn run_err_in(dir: &Path, source: &str) -> RuntimeError {
+        match run_in(dir, source) {
+            Ok(_) => panic!("Expected a runtime error."),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn imports_exported_members() {
+        let dir = module_dir(
+            "exports",
+            &[(
+                "math.lox",
+                "export var pi = 3; export fun square(x) { return x * x; } \
+                 export class Point { init(x) { this.x = x; } }",
+            )],
+        );
+        let interp = run_ok_in(
+            &dir,
+            "import \"math.lox\" as math; var a = math.square(math.pi); var b = math.Point(2).x;",
+        );
+        assert_eq!(global(&interp, "a"), LiteralValue::Number(9.0));
+        assert_eq!(global(&interp, "b"), LiteralValue::Number(2.0));
+    }
+
+    #[test]
+    fn hides_members_that_are_not_exported() {
+        let dir = module_dir(
+            "private",
+            &[("lib.lox", "var secret = 1; export var open = 2;")],
+        );
+        let err = run_err_in(&dir, "import \"lib.lox\" as lib; print lib.secret;");
+        assert_eq!(
+            err.message,
+            "Module 'lib.lox' has no export named 'secret'."
+        );
+    }
+
+    #[test]
+    fn module_functions_see_their_own_globals() {
+        let dir = module_dir(
+            "isolation",
+            &[(
+                "counter.lox",
+                "var count = 0; export fun bump() { count = count + 1; return count; }",
+            )],
+        );
+        let interp = run_ok_in(
+            &dir,
+            "var count = 100; import \"counter.lox\" as c; c.bump(); var n = c.bump();",
+        );
+        assert_eq!(global(&interp, "n"), LiteralValue::Number(2.0));
+        assert_eq!(global(&interp, "count"), LiteralValue::Number(100.0));
+    }
+
+    #[test]
+    fn loads_each_module_once() {
+        let dir = module_dir(
+            "cache",
+            &[("once.lox", "print \"loading\"; export var x = 1;")],
+        );
+        let interp = run_ok_in(
+            &dir,
+            "import \"once.lox\" as a; import \"./once.lox\" as b; var same = a == b;",
+        );
+        assert_eq!(global(&interp, "same"), LiteralValue::Boolean(true));
+    }
+
+    #[test]
+    fn resolves_nested_imports_relative_to_the_importing_file() {
+        let dir = module_dir(
+            "nested",
+            &[(
+                "main.lox",
+                "import \"sub/inner.lox\" as inner; export var v = inner.v + 1;",
+            )],
+        );
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub/inner.lox"), "export var v = 1;").unwrap();
+        let interp = run_ok_in(&dir, "import \"main.lox\" as m; var v = m.v;");
+        assert_eq!(global(&interp, "v"), LiteralValue::Number(2.0));
+    }
+
+    #[test]
+    fn reports_import_cycles_and_missing_modules() {
+        let dir = module_dir(
+            "cycle",
+            &[
+                ("a.lox", "import \"b.lox\" as b;"),
+                ("b.lox", "import \"a.lox\" as a;"),
+            ],
+        );
+        let err = run_err_in(&dir, "import \"a.lox\" as a;");
+        assert!(err.message.starts_with("Import cycle: "), "{}", err.message);
+        assert!(err.message.ends_with("a.lox."), "{}", err.message);
+
+        let err = run_err_in(&dir, "import \"missing.lox\" as m;");
+        assert!(err.message.starts_with("Can't open module 'missing.lox'"));
+    }
+
+    #[test]
+    fn rejects_assignment_to_constant_at_runtime() {
+        // The resolver only sees one REPL line, so the environment catches these.
+        let mut interp = run("const limit = 1;");
+        for source in [
+            "fun f() { limit = 2; } f();",
+            "fun g() { limit += 1; } g();",
+        ] {
+            let (stmts, _) = compile(source);
+            let err = interp.interpret(&stmts).unwrap_err();
+            assert_eq!(err.message, "Can't assign to constant 'limit'.");
+            assert_eq!(err.line, 1);
+        }
+    }
+
+    #[test]
+    fn constants_are_readable_but_not_redeclarable_as_globals() {
+        let mut interp = run("const a = 2; var b = a * 3; var c = 1;");
+        assert_eq!(global(&interp, "b"), LiteralValue::Number(6.0));
+        // Each REPL line is resolved on its own, so only the interpreter sees the clash.
+        for source in [
+            "var a = 5;",
+            "fun a() {}",
+            "class a {}",
+            "var (a, d) = (1, 2);",
+        ] {
+            let (stmts, _) = compile(source);
+            let error = interp.interpret(&stmts).unwrap_err();
+            assert_eq!(error.message, "Can't redeclare constant 'a'.", "{}", source);
+        }
+        let (stmts, _) = compile("const c = 2;");
+        let error = interp.interpret(&stmts).unwrap_err();
+        assert_eq!(error.message, "Can't redeclare constant 'c'.");
+        assert_eq!(global(&interp, "a"), LiteralValue::Number(2.0));
+    }
+
+    #[test]
+    fn destructures_tuples_and_lists() {
+        let interp = run("var (a, b) = (1, 2); (a, b) = (b, a); \
+             var ((x, y), z) = [(3, 4), 5]; \
+             var t = (a, b); var same = t == (2, 1);");
+        assert_eq!(global(&interp, "a"), LiteralValue::Number(2.0));
+        assert_eq!(global(&interp, "b"), LiteralValue::Number(1.0));
+        assert_eq!(global(&interp, "x"), LiteralValue::Number(3.0));
+        assert_eq!(global(&interp, "z"), LiteralValue::Number(5.0));
+        assert_eq!(global(&interp, "same"), LiteralValue::Boolean(true));
+    }
+
+    #[test]
+    fn destructuring_assignment_yields_the_value() {
+        let interp =
+            run("var a; var b; var t = (a, b) = (1, 2); { var c; var d; (c, d) = t; a = c + d; }");
+        assert_eq!(global(&interp, "a"), LiteralValue::Number(3.0));
+        assert_eq!(global(&interp, "b"), LiteralValue::Number(2.0));
+    }
+
+    #[test]
+    fn reports_destructuring_count_mismatch() {
+        let err = run_err("var (a, b) = (1, 2, 3);");
+        assert_eq!(err.message, "Expected 2 values to destructure but got 3.");
+
+        let err = run_err("var a = 0; var b = 0; var c = 0;\n(a, (b, c)) = (1, [2]);");
+        assert_eq!(err.message, "Expected 2 values to destructure but got 1.");
+        assert_eq!(err.line, 2);
+
+        let err = run_err("var (a, b) = 1;");
+        assert_eq!(
+            err.message,
+            "Only tuples and lists can be destructured, got 1."
+        );
+    }
+
+    #[test]
+    fn destructuring_mismatch_assigns_nothing() {
+        let (stmts, locals) = compile("var a = 0; var b = 0; (a, (b, b)) = (1, (2, 3, 4));");
+        let mut interp = Interpreter::new();
+        interp.resolve(locals);
+        assert!(interp.interpret(&stmts).is_err());
+        assert_eq!(global(&interp, "a"), LiteralValue::Number(0.0));
+    }
+
+    #[test]
+    fn const_destructuring_rejects_reassignment_at_runtime() {
+        let mut interp = run("const (x, y) = (1, 2);");
+        let (stmts, _) = compile("fun f() { x = 3; } f();");
+        let err = interp.interpret(&stmts).unwrap_err();
+        assert_eq!(err.message, "Can't assign to constant 'x'.");
+    }
+
+    #[test]
+    fn formats_self_referencing_containers() {
+        let interp = run(
+            "var a = [1]; push(a, a); var m = {}; m[\"self\"] = m; m[\"list\"] = a; \
+             var s = \"${a}\"; var t = \"${m}\";",
+        );
+        assert_eq!(
+            global(&interp, "s"),
+            LiteralValue::String("[1, [...]]".to_string())
+        );
+        assert_eq!(
+            global(&interp, "t"),
+            LiteralValue::String("{self: {...}, list: [1, [...]]}".to_string())
+        );
+    }
+
+    #[test]
+    fn modules_cannot_reassign_builtins() {
+        let dir = module_dir(
+            "builtins",
+            &[
+                ("clobber.lox", "export fun clobber() { clock = 2; }"),
+                ("reader.lox", "export var kind = \"${clock}\";"),
+            ],
+        );
+        let err = run_err_in(&dir, "import \"clobber.lox\" as c; c.clobber();");
+        assert_eq!(err.message, "Can't assign to builtin 'clock'.");
+
+        let interp = run_ok_in(
+            &dir,
+            "import \"clobber.lox\" as c; try { c.clobber(); } catch (e) {} \
+             import \"reader.lox\" as r; var kind = r.kind;",
+        );
+        assert_eq!(
+            global(&interp, "kind"),
+            LiteralValue::String("<native fn clock>".to_string())
+        );
+        assert_eq!(
+            run_err("clock = 2;").message,
+            "Can't assign to builtin 'clock'."
+        );
+        assert_eq!(run_err("clocks = 2;").message, "Undefined variable: clocks");
+        let interp = run("var clock = 1; clock = 2;");
+        assert_eq!(global(&interp, "clock"), LiteralValue::Number(2.0));
+    }
+
+    #[test]
+    fn unresolved_super_is_an_internal_error() {
+        let (stmts, _) = compile(
+            "class A { m() { return 1; } } class B < A { m() { return super.m(); } } B().m();",
+        );
+        // Skip handing the resolver's output to the interpreter.
+        let mut interp = Interpreter::new();
+        let err = interp.interpret(&stmts).err().unwrap();
+        assert_eq!(err.message, "Internal error: 'super' was not resolved.");
+    }
+}
@@ -1,13 +1,23 @@
 use std::{
     env, fs,
     io::{self, Write},
+    path::Path,
     process::ExitCode,
 };
 
-use crate::{ast_printer::AstPrinter, intrepreter::Interpreter, parser::Parser, scanner::Scanner};
+use crate::{
+    ast_printer::AstPrinter, intrepreter::Interpreter, parser::Parser, resolver::Resolver,
+    scanner::Scanner,
+};
 mod ast_printer;
+mod callable;
+mod class;
 mod intrepreter;
+mod map;
+mod module;
+mod natives;
 mod parser;
+mod resolver;
 mod scanner;
 mod token;
 
@@ -34,7 +44,8 @@ fn run_file(path: &str) -> Result<(), ExitCode> {
         eprintln!("Error reading {path}: {e}");
         ExitCode::from(65)
     })?;
-    run_source(&src).map_err(|_| ExitCode::from(65))
+    let base_dir = Path::new(path).parent().unwrap_or(Path::new("."));
+    run_source(&src, base_dir).map_err(|_| ExitCode::from(65))
 }
 
 fn repl() -> Result<(), ExitCode> {
@@ -51,28 +62,46 @@ fn repl() -> Result<(), ExitCode> {
             break; // EOF
         }
 
+        // Errors are reported and the session carries on with the next line.
         let mut scanner = Scanner::new(line.to_owned());
         let tokens = scanner.scan_tokens();
         if scanner.has_error() {
-            return Err(ExitCode::FAILURE);
+            continue;
         }
 
         let mut parser = Parser::new(tokens);
-        let stmts = parser.parse().map_err(|e| {
-            eprintln!("{}", e);
-            ExitCode::FAILURE
-        })?;
+        let stmts = match parser.parse() {
+            Ok(stmts) => stmts,
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            }
+        };
+        print_warnings(&parser);
+
+        match Resolver::new().resolve(&stmts) {
+            Ok(locals) => interp.resolve(locals),
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            }
+        }
 
-        interp.interpret(&stmts).map_err(|e| {
+        if let Err(e) = interp.interpret(&stmts) {
             eprintln!("[line {}] Error: {}", e.line, e.message);
-            ExitCode::FAILURE
-        })?
+        }
     }
     Ok(())
 }
 
-/// Scan → parse → interpret one chunk of Lox source.
-fn run_source(src: &str) -> Result<(), ()> {
+fn print_warnings(parser: &Parser) {
+    for warning in parser.warnings() {
+        eprintln!("[line {}] Warning: {}", warning.token.line, warning.message);
+    }
+}
+
+/// Scan → parse → interpret one chunk of Lox source. Imports resolve against `base_dir`.
+fn run_source(src: &str, base_dir: &Path) -> Result<(), ()> {
     let mut scanner = Scanner::new(src.to_owned());
     let tokens = scanner.scan_tokens();
     if scanner.has_error() {
@@ -82,15 +111,21 @@ fn run_source(src: &str) -> Result<(), ()> {
     let mut parser = Parser::new(tokens);
     let stmts = parser.parse().map_err(|e| {
         eprintln!("{}", e);
-        ()
+    })?;
+    print_warnings(&parser);
+
+    let locals = Resolver::new().resolve(&stmts).map_err(|e| {
+        eprintln!("{}", e);
     })?;
 
-    let mut ast_printer = AstPrinter::new();
+    let ast_printer = AstPrinter::new();
     for stmt in &stmts {
-        println!("{}", stmt.accept(&mut ast_printer));
+        println!("{}", stmt.accept(&ast_printer));
     }
 
     let mut interp = Interpreter::new();
+    interp.set_base_dir(base_dir);
+    interp.resolve(locals);
     interp.interpret(&stmts).map_err(|e| {
         eprintln!("[line {}] Error: {}", e.line, e.message);
     })
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::intrepreter::RuntimeError;
+use crate::token::{LiteralValue, write_element};
+
+/// The hashable subset of `LiteralValue`: numbers, strings, booleans and nil.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    Number(u64),
+    String(String),
+    Boolean(bool),
+    Nil,
+}
+
+impl MapKey {
+    pub fn new(value: &LiteralValue, line: usize) -> Result<Self, RuntimeError> {
+        match value {
+            // Normalise -0.0 so it hashes like 0.0, matching `==`.
+            LiteralValue::Number(n) if *n == 0.0 => Ok(MapKey::Number(0f64.to_bits())),
+            // NaN never equals itself, so an entry stored under it could never be found again.
+            LiteralValue::Number(n) if n.is_nan() => Err(RuntimeError::unhashable_key(value, line)),
+            LiteralValue::Number(n) => Ok(MapKey::Number(n.to_bits())),
+            LiteralValue::String(s) => Ok(MapKey::String(s.clone())),
+            LiteralValue::Boolean(b) => Ok(MapKey::Boolean(*b)),
+            LiteralValue::Nil => Ok(MapKey::Nil),
+            other => Err(RuntimeError::unhashable_key(other, line)),
+        }
+    }
+}
+
+/// A Lox map. Entries keep their insertion order so `keys` and printing are stable.
+#[derive(Default)]
+pub struct LoxMap {
+    entries: Vec<(LiteralValue, LiteralValue)>,
+    index: HashMap<MapKey, usize>,
+}
+
+impl LoxMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn get(&self, key: &MapKey) -> Option<&LiteralValue> {
+        self.index.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    pub fn contains(&self, key: &MapKey) -> bool {
+        self.index.contains_key(key)
+    }
+
+    /// Insert or overwrite the entry for `key`; `original` is the value the key came from.
+    pub fn insert(&mut self, key: MapKey, original: LiteralValue, value: LiteralValue) {
+        match self.index.get(&key) {
+            Some(&i) => self.entries[i].1 = value,
+            None => {
+                self.index.insert(key, self.entries.len());
+                self.entries.push((original, value));
+            }
+        }
+    }
+
+    pub fn keys(&self) -> Vec<LiteralValue> {
+        self.entries.iter().map(|(k, _)| k.clone()).collect()
+    }
+
+    pub fn values(&self) -> Vec<LiteralValue> {
+        self.entries.iter().map(|(_, v)| v.clone()).collect()
+    }
+}
+
+impl fmt::Display for LoxMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, (key, value)) in self.entries.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write_element(f, key)?;
+            write!(f, ": ")?;
+            write_element(f, value)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl fmt::Debug for LoxMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
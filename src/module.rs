@@ -0,0 +1,83 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+
+use crate::intrepreter::{Environment, RuntimeError};
+use crate::parser::{ExprId, Parser, Stmt};
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
+use crate::token::{LiteralValue, Token};
+
+/// A file loaded with `import`: the environment its top level ran in and the names it
+/// exports. Members are read live, so later updates inside the module are visible.
+pub struct LoxModule {
+    path: String,
+    environment: Rc<RefCell<Environment>>,
+    exports: HashSet<String>,
+}
+
+impl LoxModule {
+    pub fn new(
+        path: &str,
+        environment: Rc<RefCell<Environment>>,
+        exports: HashSet<String>,
+    ) -> Self {
+        LoxModule {
+            path: path.to_string(),
+            environment,
+            exports,
+        }
+    }
+
+    pub fn get(&self, name: &Token) -> Result<LiteralValue, RuntimeError> {
+        if self.exports.contains(&name.lexeme)
+            && let Some(value) = self.environment.borrow().get(&name.lexeme)
+        {
+            return Ok(value);
+        }
+        Err(RuntimeError::not_exported(
+            &name.lexeme,
+            &self.path,
+            name.line,
+        ))
+    }
+}
+
+/// Scan, parse and resolve a module's source. Errors are flattened into a message that
+/// names the module, since they surface at the `import` that loaded it.
+pub fn compile(source: &str, path: &str) -> Result<(Vec<Stmt>, HashMap<ExprId, usize>), String> {
+    let mut scanner = Scanner::new(source.to_string());
+    let tokens = scanner.scan_tokens();
+    if scanner.has_error() {
+        return Err(format!("Failed to scan module '{}'.", path));
+    }
+
+    let mut parser = Parser::new(tokens);
+    let statements = parser
+        .parse()
+        .map_err(|e| format!("In module '{}': {}", path, e))?;
+    for warning in parser.warnings() {
+        eprintln!(
+            "[{} line {}] Warning: {}",
+            path, warning.token.line, warning.message
+        );
+    }
+
+    let locals = Resolver::new()
+        .resolve(&statements)
+        .map_err(|e| format!("In module '{}': {}", path, e))?;
+    Ok((statements, locals))
+}
+
+impl fmt::Display for LoxModule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<module {}>", self.path)
+    }
+}
+
+impl fmt::Debug for LoxModule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
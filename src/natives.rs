@@ -0,0 +1,81 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::intrepreter::{Interpreter, RuntimeError};
+use crate::map::MapKey;
+use crate::token::LiteralValue;
+
+/// Define the built-in global functions every interpreter starts with.
+pub fn register_builtins(interpreter: &mut Interpreter) {
+    interpreter.register_native("clock", 0, |_| {
+        let elapsed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Ok(LiteralValue::Number(elapsed.as_secs_f64()))
+    });
+
+    interpreter.register_native("len", 1, |args| match &args[0] {
+        LiteralValue::List(list) => Ok(LiteralValue::Number(list.borrow().len() as f64)),
+        LiteralValue::Map(map) => Ok(LiteralValue::Number(map.borrow().len() as f64)),
+        LiteralValue::String(s) => Ok(LiteralValue::Number(s.chars().count() as f64)),
+        other => Err(RuntimeError::native(&format!(
+            "len() expects a list, map or string, got {}.",
+            other
+        ))),
+    });
+
+    interpreter.register_native("push", 2, |args| match &args[0] {
+        LiteralValue::List(list) => {
+            list.borrow_mut().push(args[1].clone());
+            Ok(LiteralValue::Nil)
+        }
+        other => Err(RuntimeError::native(&format!(
+            "push() expects a list, got {}.",
+            other
+        ))),
+    });
+
+    interpreter.register_native("pop", 1, |args| match &args[0] {
+        LiteralValue::List(list) => list
+            .borrow_mut()
+            .pop()
+            .ok_or_else(|| RuntimeError::native("Can't pop from an empty list.")),
+        other => Err(RuntimeError::native(&format!(
+            "pop() expects a list, got {}.",
+            other
+        ))),
+    });
+
+    interpreter.register_native("keys", 1, |args| match &args[0] {
+        LiteralValue::Map(map) => Ok(list(map.borrow().keys())),
+        other => Err(RuntimeError::native(&format!(
+            "keys() expects a map, got {}.",
+            other
+        ))),
+    });
+
+    interpreter.register_native("values", 1, |args| match &args[0] {
+        LiteralValue::Map(map) => Ok(list(map.borrow().values())),
+        other => Err(RuntimeError::native(&format!(
+            "values() expects a map, got {}.",
+            other
+        ))),
+    });
+
+    interpreter.register_native("has", 2, |args| match &args[0] {
+        LiteralValue::Map(map) => {
+            let key = MapKey::new(&args[1], 0)?;
+            Ok(LiteralValue::Boolean(map.borrow().contains(&key)))
+        }
+        other => Err(RuntimeError::native(&format!(
+            "has() expects a map, got {}.",
+            other
+        ))),
+    });
+}
+
+fn list(elements: Vec<LiteralValue>) -> LiteralValue {
+    LiteralValue::List(Rc::new(RefCell::new(elements)))
+}
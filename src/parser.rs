@@ -1,4 +1,6 @@
 use std::fmt::Display;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::token::{LiteralValue, Token, TokenType};
 
@@ -6,15 +8,43 @@ pub enum Stmt {
     Expr(Expr),
     Print(Expr),
     Variable(VarAssignment),
+    Destructure(VarDestructure),
+    Block(Vec<Stmt>),
+    If(If),
+    While(While),
+    Function(Rc<Function>),
+    Return(Return),
+    Class(Class),
+    Break { keyword: Token },
+    Continue { keyword: Token },
+    Match(Match),
+    Throw(Throw),
+    Try(Try),
+    Import(Import),
+    Export(Export),
 }
 
 impl Stmt {
     #[allow(dead_code)]
-    pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> T {
+    pub fn accept<T>(&self, visitor: &dyn StmtVisitor<T>) -> T {
         match self {
             Stmt::Expr(expr) => visitor.visit_expr(expr),
             Stmt::Print(expr) => visitor.visit_print(expr),
             Stmt::Variable(var) => visitor.visit_variable(var),
+            Stmt::Destructure(stmt) => visitor.visit_var_destructure(stmt),
+            Stmt::Block(stmts) => visitor.visit_block(stmts),
+            Stmt::If(stmt) => visitor.visit_if(stmt),
+            Stmt::While(stmt) => visitor.visit_while(stmt),
+            Stmt::Function(stmt) => visitor.visit_function(stmt),
+            Stmt::Return(stmt) => visitor.visit_return(stmt),
+            Stmt::Class(stmt) => visitor.visit_class(stmt),
+            Stmt::Break { keyword } => visitor.visit_break(keyword),
+            Stmt::Continue { keyword } => visitor.visit_continue(keyword),
+            Stmt::Match(stmt) => visitor.visit_match(stmt),
+            Stmt::Throw(stmt) => visitor.visit_throw(stmt),
+            Stmt::Try(stmt) => visitor.visit_try(stmt),
+            Stmt::Import(stmt) => visitor.visit_import(stmt),
+            Stmt::Export(stmt) => visitor.visit_export(stmt),
         }
     }
 
@@ -23,78 +53,378 @@ impl Stmt {
             Stmt::Expr(expr) => visitor.visit_expr(expr),
             Stmt::Print(expr) => visitor.visit_print(expr),
             Stmt::Variable(var) => visitor.visit_variable(var),
+            Stmt::Destructure(stmt) => visitor.visit_var_destructure(stmt),
+            Stmt::Block(stmts) => visitor.visit_block(stmts),
+            Stmt::If(stmt) => visitor.visit_if(stmt),
+            Stmt::While(stmt) => visitor.visit_while(stmt),
+            Stmt::Function(stmt) => visitor.visit_function(stmt),
+            Stmt::Return(stmt) => visitor.visit_return(stmt),
+            Stmt::Class(stmt) => visitor.visit_class(stmt),
+            Stmt::Break { keyword } => visitor.visit_break(keyword),
+            Stmt::Continue { keyword } => visitor.visit_continue(keyword),
+            Stmt::Match(stmt) => visitor.visit_match(stmt),
+            Stmt::Throw(stmt) => visitor.visit_throw(stmt),
+            Stmt::Try(stmt) => visitor.visit_try(stmt),
+            Stmt::Import(stmt) => visitor.visit_import(stmt),
+            Stmt::Export(stmt) => visitor.visit_export(stmt),
         }
     }
 }
 
+/// `var (a, b) = value;` and its `const` form.
+pub struct VarDestructure {
+    pub target: Target,
+    pub initializer: Box<Expr>,
+    pub constant: bool,
+}
+
 #[derive(Debug)]
 pub struct VarAssignment {
     pub token: Token,
     pub initializer: Option<Box<Expr>>,
+    /// Declared with `const`: the initializer is required and the name can't be reassigned.
+    pub constant: bool,
+}
+
+pub struct If {
+    pub condition: Expr,
+    pub then_branch: Box<Stmt>,
+    pub else_branch: Option<Box<Stmt>>,
+}
+
+pub struct While {
+    pub condition: Expr,
+    pub body: Box<Stmt>,
+    /// The increment clause of a lowered `for` loop. Kept apart from the body so that
+    /// `continue` still runs it.
+    pub increment: Option<Expr>,
+}
+
+/// A function declaration, or the body of an anonymous function (named `lambda`).
+/// Shared via `Rc` so closures can hold on to it.
+pub struct Function {
+    pub name: Token,
+    pub params: Vec<Token>,
+    pub body: Vec<Stmt>,
+}
+
+// `Stmt` has no `Debug`, so show just the signature; `Expr::Lambda` needs this.
+impl std::fmt::Debug for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let params: Vec<&str> = self.params.iter().map(|p| p.lexeme.as_str()).collect();
+        f.debug_struct("Function")
+            .field("name", &self.name.lexeme)
+            .field("params", &params)
+            .finish_non_exhaustive()
+    }
+}
+
+pub struct Return {
+    pub keyword: Token,
+    pub value: Option<Expr>,
+}
+
+/// `match value { pattern => expr, ... }`: runs the first arm whose pattern matches.
+pub struct Match {
+    pub value: Expr,
+    pub arms: Vec<MatchArm>,
+}
+
+pub struct MatchArm {
+    /// Alternatives separated by `|`; the arm applies if any of them matches.
+    pub patterns: Vec<Pattern>,
+    pub guard: Option<Expr>,
+    pub body: Box<Stmt>,
+}
+
+#[derive(Debug)]
+pub enum Pattern {
+    Literal(LiteralValue),
+    /// `start..end`, or `start..=end` when `inclusive`.
+    Range {
+        start: f64,
+        end: f64,
+        inclusive: bool,
+    },
+    Wildcard,
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Literal(value) => write!(f, "{}", value),
+            Pattern::Range {
+                start,
+                end,
+                inclusive,
+            } => write!(
+                f,
+                "{}{}{}",
+                start,
+                if *inclusive { "..=" } else { ".." },
+                end
+            ),
+            Pattern::Wildcard => write!(f, "_"),
+        }
+    }
+}
+
+pub struct Throw {
+    pub keyword: Token,
+    pub value: Expr,
+}
+
+/// `try { } catch (e) { } finally { }`; at least one of `catch` and `finally` is present.
+pub struct Try {
+    pub body: Vec<Stmt>,
+    pub catch: Option<Catch>,
+    pub finally: Option<Vec<Stmt>>,
+}
+
+pub struct Catch {
+    pub name: Token,
+    pub body: Vec<Stmt>,
+}
+
+/// `import "path" as name;`. The path is relative to the importing file.
+pub struct Import {
+    pub keyword: Token,
+    pub path: String,
+    pub name: Token,
+}
+
+/// `export` in front of a top-level `var`, `const`, `fun` or `class` declaration.
+pub struct Export {
+    pub keyword: Token,
+    pub declaration: Box<Stmt>,
+}
+
+impl Export {
+    /// The names the wrapped declaration binds.
+    pub fn names(&self) -> Vec<&Token> {
+        match self.declaration.as_ref() {
+            Stmt::Variable(var) => vec![&var.token],
+            Stmt::Destructure(stmt) => stmt
+                .target
+                .variables()
+                .into_iter()
+                .map(|(_, name)| name)
+                .collect(),
+            Stmt::Function(function) => vec![&function.name],
+            Stmt::Class(class) => vec![&class.name],
+            _ => unreachable!("the parser only exports declarations"),
+        }
+    }
+
+    /// Whether the wrapped declaration is a `const`.
+    pub fn is_constant(&self) -> bool {
+        match self.declaration.as_ref() {
+            Stmt::Variable(var) => var.constant,
+            Stmt::Destructure(stmt) => stmt.constant,
+            _ => false,
+        }
+    }
+}
+
+pub struct Class {
+    pub name: Token,
+    /// Always an `Expr::Variable` naming the superclass.
+    pub superclass: Option<Expr>,
+    pub methods: Vec<Rc<Function>>,
+}
+
+/// Identifies an expression that refers to a variable, so the resolver can record
+/// how many scopes away its binding lives.
+pub type ExprId = usize;
+
+/// Ids are unique across parsers, so code parsed line by line in the REPL never collides.
+fn next_expr_id() -> ExprId {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
 }
 
 #[derive(Debug)]
 pub enum Expr {
     Binary(Binary),
+    Logical(Logical),
+    Conditional(Conditional),
     Unary(Unary),
+    Call(Call),
+    Lambda(Rc<Function>),
     Grouping(Grouping),
     Literal(Literal),
-    Variable { token: Token },
-    Assign { token: Token, value: Box<Expr> },
+    Variable {
+        id: ExprId,
+        token: Token,
+    },
+    Assign {
+        id: ExprId,
+        token: Token,
+        value: Box<Expr>,
+    },
+    Get(Get),
+    Set(Set),
+    This {
+        id: ExprId,
+        keyword: Token,
+    },
+    Super(Super),
+    List(List),
+    Index(Index),
+    IndexSet(IndexSet),
+    Compound(Compound),
+    Map(Map),
+    Interpolation(Interpolation),
+    Tuple(Tuple),
+    Destructure(Destructure),
 }
 
 impl Expr {
     pub fn accept<T>(&self, visitor: &dyn ExprVisitor<T>) -> T {
         match self {
             Expr::Binary(expr) => visitor.visit_binary(expr),
+            Expr::Logical(expr) => visitor.visit_logical(expr),
+            Expr::Conditional(expr) => visitor.visit_conditional(expr),
             Expr::Unary(expr) => visitor.visit_unary(expr),
+            Expr::Call(expr) => visitor.visit_call(expr),
+            Expr::Lambda(function) => visitor.visit_lambda(function),
             Expr::Grouping(expr) => visitor.visit_grouping(expr),
             Expr::Literal(expr) => visitor.visit_literal(expr),
-            Expr::Variable { token } => visitor.visit_variable(token),
-            Expr::Assign { token, value } => visitor.visit_assign(token, value),
+            Expr::Variable { id, token } => visitor.visit_variable(*id, token),
+            Expr::Assign { id, token, value } => visitor.visit_assign(*id, token, value),
+            Expr::Get(expr) => visitor.visit_get(expr),
+            Expr::Set(expr) => visitor.visit_set(expr),
+            Expr::This { id, keyword } => visitor.visit_this(*id, keyword),
+            Expr::Super(expr) => visitor.visit_super(expr),
+            Expr::List(expr) => visitor.visit_list(expr),
+            Expr::Index(expr) => visitor.visit_index(expr),
+            Expr::IndexSet(expr) => visitor.visit_index_set(expr),
+            Expr::Compound(expr) => visitor.visit_compound(expr),
+            Expr::Map(expr) => visitor.visit_map(expr),
+            Expr::Interpolation(expr) => visitor.visit_interpolation(expr),
+            Expr::Tuple(expr) => visitor.visit_tuple(expr),
+            Expr::Destructure(expr) => visitor.visit_destructure(expr),
         }
     }
 
     pub fn accept_mut<T>(&self, visitor: &mut dyn ExprVisitorMut<T>) -> T {
         match self {
             Expr::Binary(expr) => visitor.visit_binary(expr),
+            Expr::Logical(expr) => visitor.visit_logical(expr),
+            Expr::Conditional(expr) => visitor.visit_conditional(expr),
             Expr::Unary(expr) => visitor.visit_unary(expr),
+            Expr::Call(expr) => visitor.visit_call(expr),
+            Expr::Lambda(function) => visitor.visit_lambda(function),
             Expr::Grouping(expr) => visitor.visit_grouping(expr),
             Expr::Literal(expr) => visitor.visit_literal(expr),
-            Expr::Variable { token } => visitor.visit_variable(token),
-            Expr::Assign { token, value } => visitor.visit_assign(token, value),
+            Expr::Variable { id, token } => visitor.visit_variable(*id, token),
+            Expr::Assign { id, token, value } => visitor.visit_assign(*id, token, value),
+            Expr::Get(expr) => visitor.visit_get(expr),
+            Expr::Set(expr) => visitor.visit_set(expr),
+            Expr::This { id, keyword } => visitor.visit_this(*id, keyword),
+            Expr::Super(expr) => visitor.visit_super(expr),
+            Expr::List(expr) => visitor.visit_list(expr),
+            Expr::Index(expr) => visitor.visit_index(expr),
+            Expr::IndexSet(expr) => visitor.visit_index_set(expr),
+            Expr::Compound(expr) => visitor.visit_compound(expr),
+            Expr::Map(expr) => visitor.visit_map(expr),
+            Expr::Interpolation(expr) => visitor.visit_interpolation(expr),
+            Expr::Tuple(expr) => visitor.visit_tuple(expr),
+            Expr::Destructure(expr) => visitor.visit_destructure(expr),
         }
     }
 }
 
 pub trait ExprVisitor<T> {
     fn visit_binary(&self, expr: &Binary) -> T;
+    fn visit_logical(&self, expr: &Logical) -> T;
+    fn visit_conditional(&self, expr: &Conditional) -> T;
     fn visit_unary(&self, expr: &Unary) -> T;
+    fn visit_call(&self, expr: &Call) -> T;
+    fn visit_lambda(&self, function: &Rc<Function>) -> T;
     fn visit_grouping(&self, expr: &Grouping) -> T;
     fn visit_literal(&self, expr: &Literal) -> T;
-    fn visit_variable(&self, token: &Token) -> T;
-    fn visit_assign(&self, token: &Token, value: &Box<Expr>) -> T;
+    fn visit_variable(&self, id: ExprId, token: &Token) -> T;
+    #[allow(clippy::borrowed_box)]
+    fn visit_assign(&self, id: ExprId, token: &Token, value: &Box<Expr>) -> T;
+    fn visit_get(&self, expr: &Get) -> T;
+    fn visit_set(&self, expr: &Set) -> T;
+    fn visit_this(&self, id: ExprId, keyword: &Token) -> T;
+    fn visit_super(&self, expr: &Super) -> T;
+    fn visit_list(&self, expr: &List) -> T;
+    fn visit_index(&self, expr: &Index) -> T;
+    fn visit_index_set(&self, expr: &IndexSet) -> T;
+    fn visit_compound(&self, expr: &Compound) -> T;
+    fn visit_map(&self, expr: &Map) -> T;
+    fn visit_interpolation(&self, expr: &Interpolation) -> T;
+    fn visit_tuple(&self, expr: &Tuple) -> T;
+    fn visit_destructure(&self, expr: &Destructure) -> T;
 }
 
 pub trait ExprVisitorMut<T> {
     fn visit_binary(&mut self, expr: &Binary) -> T;
+    fn visit_logical(&mut self, expr: &Logical) -> T;
+    fn visit_conditional(&mut self, expr: &Conditional) -> T;
     fn visit_unary(&mut self, expr: &Unary) -> T;
+    fn visit_call(&mut self, expr: &Call) -> T;
+    fn visit_lambda(&mut self, function: &Rc<Function>) -> T;
     fn visit_grouping(&mut self, expr: &Grouping) -> T;
     fn visit_literal(&mut self, expr: &Literal) -> T;
-    fn visit_variable(&mut self, token: &Token) -> T;
-    fn visit_assign(&mut self, token: &Token, value: &Box<Expr>) -> T;
+    fn visit_variable(&mut self, id: ExprId, token: &Token) -> T;
+    #[allow(clippy::borrowed_box)]
+    fn visit_assign(&mut self, id: ExprId, token: &Token, value: &Box<Expr>) -> T;
+    fn visit_get(&mut self, expr: &Get) -> T;
+    fn visit_set(&mut self, expr: &Set) -> T;
+    fn visit_this(&mut self, id: ExprId, keyword: &Token) -> T;
+    fn visit_super(&mut self, expr: &Super) -> T;
+    fn visit_list(&mut self, expr: &List) -> T;
+    fn visit_index(&mut self, expr: &Index) -> T;
+    fn visit_index_set(&mut self, expr: &IndexSet) -> T;
+    fn visit_compound(&mut self, expr: &Compound) -> T;
+    fn visit_map(&mut self, expr: &Map) -> T;
+    fn visit_interpolation(&mut self, expr: &Interpolation) -> T;
+    fn visit_tuple(&mut self, expr: &Tuple) -> T;
+    fn visit_destructure(&mut self, expr: &Destructure) -> T;
 }
 
 pub trait StmtVisitor<T> {
     fn visit_expr(&self, expr: &Expr) -> T;
     fn visit_print(&self, expr: &Expr) -> T;
     fn visit_variable(&self, var: &VarAssignment) -> T;
+    fn visit_var_destructure(&self, stmt: &VarDestructure) -> T;
+    fn visit_block(&self, stmts: &[Stmt]) -> T;
+    fn visit_if(&self, stmt: &If) -> T;
+    fn visit_while(&self, stmt: &While) -> T;
+    fn visit_function(&self, stmt: &Rc<Function>) -> T;
+    fn visit_return(&self, stmt: &Return) -> T;
+    fn visit_class(&self, stmt: &Class) -> T;
+    fn visit_break(&self, keyword: &Token) -> T;
+    fn visit_continue(&self, keyword: &Token) -> T;
+    fn visit_match(&self, stmt: &Match) -> T;
+    fn visit_throw(&self, stmt: &Throw) -> T;
+    fn visit_try(&self, stmt: &Try) -> T;
+    fn visit_import(&self, stmt: &Import) -> T;
+    fn visit_export(&self, stmt: &Export) -> T;
 }
 
 pub trait StmtVisitorMut<T> {
     fn visit_expr(&mut self, expr: &Expr) -> T;
     fn visit_print(&mut self, expr: &Expr) -> T;
     fn visit_variable(&mut self, var: &VarAssignment) -> T;
+    fn visit_var_destructure(&mut self, stmt: &VarDestructure) -> T;
+    fn visit_block(&mut self, stmts: &[Stmt]) -> T;
+    fn visit_if(&mut self, stmt: &If) -> T;
+    fn visit_while(&mut self, stmt: &While) -> T;
+    fn visit_function(&mut self, stmt: &Rc<Function>) -> T;
+    fn visit_return(&mut self, stmt: &Return) -> T;
+    fn visit_class(&mut self, stmt: &Class) -> T;
+    fn visit_break(&mut self, keyword: &Token) -> T;
+    fn visit_continue(&mut self, keyword: &Token) -> T;
+    fn visit_match(&mut self, stmt: &Match) -> T;
+    fn visit_throw(&mut self, stmt: &Throw) -> T;
+    fn visit_try(&mut self, stmt: &Try) -> T;
+    fn visit_import(&mut self, stmt: &Import) -> T;
+    fn visit_export(&mut self, stmt: &Export) -> T;
 }
 
 #[derive(Debug)]
@@ -104,12 +434,36 @@ pub struct Binary {
     pub right: Box<Expr>,
 }
 
+/// A short-circuiting `and` / `or` expression.
+#[derive(Debug)]
+pub struct Logical {
+    pub left: Box<Expr>,
+    pub operator: Token,
+    pub right: Box<Expr>,
+}
+
+/// A ternary conditional: `condition ? then_branch : else_branch`.
+#[derive(Debug)]
+pub struct Conditional {
+    pub condition: Box<Expr>,
+    pub then_branch: Box<Expr>,
+    pub else_branch: Box<Expr>,
+}
+
 #[derive(Debug)]
 pub struct Unary {
     pub operator: Token,
     pub right: Box<Expr>,
 }
 
+#[derive(Debug)]
+pub struct Call {
+    pub callee: Box<Expr>,
+    /// The closing parenthesis, kept for its line in runtime errors.
+    pub paren: Token,
+    pub arguments: Vec<Expr>,
+}
+
 #[derive(Debug)]
 pub struct Grouping {
     pub expr: Box<Expr>,
@@ -120,6 +474,130 @@ pub struct Literal {
     pub value: LiteralValue,
 }
 
+/// Property access: `object.name`.
+#[derive(Debug)]
+pub struct Get {
+    pub object: Box<Expr>,
+    pub name: Token,
+}
+
+/// Property assignment: `object.name = value`.
+#[derive(Debug)]
+pub struct Set {
+    pub object: Box<Expr>,
+    pub name: Token,
+    pub value: Box<Expr>,
+}
+
+/// Superclass method access: `super.method`.
+#[derive(Debug)]
+pub struct Super {
+    pub id: ExprId,
+    pub keyword: Token,
+    pub method: Token,
+}
+
+/// A list literal: `[a, b, c]`.
+#[derive(Debug)]
+pub struct List {
+    pub elements: Vec<Expr>,
+}
+
+/// Subscript access: `object[index]`.
+#[derive(Debug)]
+pub struct Index {
+    pub object: Box<Expr>,
+    pub bracket: Token,
+    pub index: Box<Expr>,
+}
+
+/// Subscript assignment: `object[index] = value`.
+#[derive(Debug)]
+pub struct IndexSet {
+    pub object: Box<Expr>,
+    pub bracket: Token,
+    pub index: Box<Expr>,
+    pub value: Box<Expr>,
+}
+
+/// A read-modify-write of a variable, property or element: `x += 1`, `++a.b`, `xs[i]--`.
+#[derive(Debug)]
+pub struct Compound {
+    /// An `Expr::Variable`, `Expr::Get` or `Expr::Index`; evaluated only once.
+    pub target: Box<Expr>,
+    /// The arithmetic to apply. Its type is the plain binary operator (`+` for `+=` and
+    /// `++`) while the lexeme keeps what was written.
+    pub operator: Token,
+    pub value: Box<Expr>,
+    /// `x++` / `x--` produce the value from before the update.
+    pub postfix: bool,
+}
+
+/// A map literal: `{key: value, ...}`. Only parsed in expression position; a `{` that
+/// starts a statement is always a block.
+#[derive(Debug)]
+pub struct Map {
+    pub brace: Token,
+    pub entries: Vec<(Expr, Expr)>,
+}
+
+/// An interpolated string: `"a ${b} c"`. Literal segments and embedded expressions
+/// alternate in `parts`, in source order.
+#[derive(Debug)]
+pub struct Interpolation {
+    pub parts: Vec<Expr>,
+}
+
+/// A parenthesized, comma-separated sequence: `(a, b)`.
+#[derive(Debug)]
+pub struct Tuple {
+    pub paren: Token,
+    pub elements: Vec<Expr>,
+}
+
+/// The left-hand side of a destructuring declaration or assignment. Tuple targets nest,
+/// so `((a, b), c)` unpacks a pair whose first element is itself a pair.
+#[derive(Debug)]
+pub enum Target {
+    Variable { id: ExprId, name: Token },
+    Tuple { paren: Token, elements: Vec<Target> },
+}
+
+impl Target {
+    /// Every variable the target binds, in source order.
+    pub fn variables(&self) -> Vec<(ExprId, &Token)> {
+        match self {
+            Target::Variable { id, name } => vec![(*id, name)],
+            Target::Tuple { elements, .. } => elements.iter().flat_map(Target::variables).collect(),
+        }
+    }
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Target::Variable { name, .. } => write!(f, "{}", name.lexeme),
+            Target::Tuple { elements, .. } => {
+                write!(f, "(")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// `(a, b) = value`: assigns each element of a tuple or list to an existing variable.
+#[derive(Debug)]
+pub struct Destructure {
+    pub target: Target,
+    pub value: Box<Expr>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ParseError {
     pub token: Token,
@@ -136,14 +614,27 @@ impl Display for ParseError {
     }
 }
 
+/// Upper bound on parameters and call arguments.
+const MAX_ARGUMENTS: usize = 255;
+
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    /// Problems that don't stop parsing, such as unreachable `match` arms.
+    warnings: Vec<ParseError>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Parser { tokens, current: 0 }
+        Parser {
+            tokens,
+            current: 0,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn warnings(&self) -> &[ParseError] {
+        &self.warnings
     }
 
     pub fn parse(&mut self) -> Result<Vec<Stmt>, ParseError> {
@@ -161,24 +652,477 @@ impl Parser {
     }
 
     fn declaration(&mut self) -> Result<Stmt, ParseError> {
-        if self.match_token(&[TokenType::Var]) {
+        if self.match_token(&[TokenType::Import]) {
+            return self.import_declaration();
+        }
+        if self.match_token(&[TokenType::Export]) {
+            return self.export_declaration();
+        }
+        if self.match_token(&[TokenType::Class]) {
+            return self.class_declaration();
+        }
+        // `fun (` starts an anonymous function, which is an expression statement.
+        if self.check(&TokenType::Fun) && !self.check_next(&TokenType::LeftParen) {
+            self.advance();
+            return Ok(Stmt::Function(Rc::new(self.function("function")?)));
+        }
+        if self.match_token(&[TokenType::Var, TokenType::Const]) {
             return self.var_declaration();
         }
         self.statement()
     }
 
     fn statement(&mut self) -> Result<Stmt, ParseError> {
+        if self.match_token(&[TokenType::For]) {
+            return self.for_statement();
+        }
+
+        if self.match_token(&[TokenType::If]) {
+            return self.if_statement();
+        }
+
         if self.match_token(&[TokenType::Print]) {
             return self.print_statement();
         }
 
+        if self.match_token(&[TokenType::Return]) {
+            return self.return_statement();
+        }
+
+        if self.match_token(&[TokenType::Break]) {
+            let keyword = self.previous();
+            self.consume(TokenType::SemiColon, "Expect ';' after 'break'.")?;
+            return Ok(Stmt::Break { keyword });
+        }
+
+        if self.match_token(&[TokenType::Continue]) {
+            let keyword = self.previous();
+            self.consume(TokenType::SemiColon, "Expect ';' after 'continue'.")?;
+            return Ok(Stmt::Continue { keyword });
+        }
+
+        if self.match_token(&[TokenType::While]) {
+            return self.while_statement();
+        }
+
+        if self.match_token(&[TokenType::Match]) {
+            return self.match_statement();
+        }
+
+        if self.match_token(&[TokenType::Throw]) {
+            return self.throw_statement();
+        }
+
+        if self.match_token(&[TokenType::Try]) {
+            return self.try_statement();
+        }
+
+        if self.match_token(&[TokenType::LeftBrace]) {
+            return Ok(Stmt::Block(self.block()?));
+        }
+
         self.expression_statement()
     }
 
+    /// Parse a C-style `for` loop and lower it into an equivalent `while` loop:
+    /// `{ init; while (cond) body }`, with `incr` run after each iteration.
+    fn for_statement(&mut self) -> Result<Stmt, ParseError> {
+        self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
+
+        let initializer = if self.match_token(&[TokenType::SemiColon]) {
+            None
+        } else if self.match_token(&[TokenType::Var]) {
+            Some(self.var_declaration()?)
+        } else {
+            Some(self.expression_statement()?)
+        };
+
+        let condition = if self.check(&TokenType::SemiColon) {
+            Expr::Literal(Literal {
+                value: LiteralValue::Boolean(true),
+            })
+        } else {
+            self.expression()?
+        };
+        self.consume(TokenType::SemiColon, "Expect ';' after loop condition.")?;
+
+        let increment = if self.check(&TokenType::RightParen) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(TokenType::RightParen, "Expect ')' after for clauses.")?;
+
+        let mut body = Stmt::While(While {
+            condition,
+            body: Box::new(self.statement()?),
+            increment,
+        });
+        if let Some(initializer) = initializer {
+            body = Stmt::Block(vec![initializer, body]);
+        }
+
+        Ok(body)
+    }
+
+    fn if_statement(&mut self) -> Result<Stmt, ParseError> {
+        self.consume(TokenType::LeftParen, "Expect '(' after 'if'.")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after if condition.")?;
+
+        let then_branch = Box::new(self.statement()?);
+        // An `else` binds to the nearest `if`, which resolves the dangling-else ambiguity.
+        let else_branch = if self.match_token(&[TokenType::Else]) {
+            Some(Box::new(self.statement()?))
+        } else {
+            None
+        };
+
+        Ok(Stmt::If(If {
+            condition,
+            then_branch,
+            else_branch,
+        }))
+    }
+
+    fn while_statement(&mut self) -> Result<Stmt, ParseError> {
+        self.consume(TokenType::LeftParen, "Expect '(' after 'while'.")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after condition.")?;
+        let body = Box::new(self.statement()?);
+        Ok(Stmt::While(While {
+            condition,
+            body,
+            increment: None,
+        }))
+    }
+
+    fn class_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let name = self.consume(TokenType::Identifier, "Expect class name.")?;
+
+        let superclass = if self.match_token(&[TokenType::Less]) {
+            let token = self.consume(TokenType::Identifier, "Expect superclass name.")?;
+            Some(Expr::Variable {
+                id: next_expr_id(),
+                token,
+            })
+        } else {
+            None
+        };
+
+        self.consume(TokenType::LeftBrace, "Expect '{' before class body.")?;
+        let mut methods = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            methods.push(Rc::new(self.function("method")?));
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after class body.")?;
+
+        Ok(Stmt::Class(Class {
+            name,
+            superclass,
+            methods,
+        }))
+    }
+
+    /// Parse a function's name, parameter list and body. `kind` names the construct in errors.
+    fn function(&mut self, kind: &str) -> Result<Function, ParseError> {
+        let name = self.consume(TokenType::Identifier, &format!("Expect {} name.", kind))?;
+        self.consume(
+            TokenType::LeftParen,
+            &format!("Expect '(' after {} name.", kind),
+        )?;
+        let params = self.parameters()?;
+
+        self.consume(
+            TokenType::LeftBrace,
+            &format!("Expect '{{' before {} body.", kind),
+        )?;
+        let body = self.block()?;
+
+        Ok(Function { name, params, body })
+    }
+
+    /// Parse a parameter list after its opening `(`, up to and including the `)`.
+    fn parameters(&mut self) -> Result<Vec<Token>, ParseError> {
+        let mut params = Vec::new();
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                if params.len() >= MAX_ARGUMENTS {
+                    return Err(ParseError {
+                        token: self.peek().clone(),
+                        message: format!("Can't have more than {} parameters.", MAX_ARGUMENTS),
+                    });
+                }
+                params.push(self.consume(TokenType::Identifier, "Expect parameter name.")?);
+                if !self.match_token(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
+        Ok(params)
+    }
+
+    /// Parse `fun (params) { body }` after the `fun` keyword.
+    fn lambda(&mut self) -> Result<Expr, ParseError> {
+        let keyword = self.previous();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'fun'.")?;
+        let params = self.parameters()?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before lambda body.")?;
+        let body = self.block()?;
+        Ok(Expr::Lambda(Rc::new(Function {
+            name: Self::lambda_name(&keyword),
+            params,
+            body,
+        })))
+    }
+
+    /// Parse `(params) => expr`, starting at the `(`. The body returns its expression.
+    fn arrow_function(&mut self) -> Result<Expr, ParseError> {
+        let paren = self.consume(TokenType::LeftParen, "Expect '(' before parameters.")?;
+        let params = self.parameters()?;
+        let arrow = self.consume(TokenType::Arrow, "Expect '=>' after parameters.")?;
+        let value = self.expression()?;
+        Ok(Expr::Lambda(Rc::new(Function {
+            name: Self::lambda_name(&paren),
+            params,
+            body: vec![Stmt::Return(Return {
+                keyword: arrow,
+                value: Some(value),
+            })],
+        })))
+    }
+
+    fn lambda_name(token: &Token) -> Token {
+        Token::simple(TokenType::Identifier, "lambda", token.line)
+    }
+
+    /// Whether the `(` at the current token opens an arrow function's parameter list,
+    /// i.e. it is followed by `ident, ident, ... ) =>`.
+    fn is_arrow_function(&self) -> bool {
+        let typ = |i: usize| self.tokens.get(i).map(|token| token.typ);
+        let mut i = self.current + 1;
+        if typ(i) != Some(TokenType::RightParen) {
+            loop {
+                if typ(i) != Some(TokenType::Identifier) {
+                    return false;
+                }
+                i += 1;
+                if typ(i) != Some(TokenType::Comma) {
+                    break;
+                }
+                i += 1;
+            }
+            if typ(i) != Some(TokenType::RightParen) {
+                return false;
+            }
+        }
+        typ(i + 1) == Some(TokenType::Arrow)
+    }
+
+    fn match_statement(&mut self) -> Result<Stmt, ParseError> {
+        let value = self.expression()?;
+        self.consume(TokenType::LeftBrace, "Expect '{' after match value.")?;
+
+        let mut arms = Vec::new();
+        let mut catch_all = false;
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            if catch_all {
+                self.warnings.push(ParseError {
+                    token: self.peek().clone(),
+                    message: "Unreachable match arm after a wildcard pattern.".to_string(),
+                });
+            }
+
+            let mut patterns = vec![self.pattern()?];
+            while self.match_token(&[TokenType::Pipe]) {
+                patterns.push(self.pattern()?);
+            }
+            let guard = if self.match_token(&[TokenType::If]) {
+                Some(self.expression()?)
+            } else {
+                None
+            };
+            self.consume(TokenType::Arrow, "Expect '=>' after match pattern.")?;
+            // An arm is a block, or a single expression optionally followed by a comma.
+            let body = if self.match_token(&[TokenType::LeftBrace]) {
+                Stmt::Block(self.block()?)
+            } else {
+                Stmt::Expr(self.expression()?)
+            };
+            let body = Box::new(body);
+            self.match_token(&[TokenType::Comma]);
+
+            if guard.is_none() && patterns.iter().any(|p| matches!(p, Pattern::Wildcard)) {
+                catch_all = true;
+            }
+            arms.push(MatchArm {
+                patterns,
+                guard,
+                body,
+            });
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after match arms.")?;
+
+        Ok(Stmt::Match(Match { value, arms }))
+    }
+
+    /// A literal (`1`, `-2.5`, `"a"`, `true`, `nil`), a number range (`1..5`, `1..=5`) or `_`.
+    fn pattern(&mut self) -> Result<Pattern, ParseError> {
+        if self.check(&TokenType::Identifier) && self.peek().lexeme == "_" {
+            self.advance();
+            return Ok(Pattern::Wildcard);
+        }
+        if self.check(&TokenType::Number) || self.check(&TokenType::Minus) {
+            let start = self.pattern_number()?;
+            let inclusive = if self.match_token(&[TokenType::DotDotEqual]) {
+                true
+            } else if self.match_token(&[TokenType::DotDot]) {
+                false
+            } else {
+                return Ok(Pattern::Literal(LiteralValue::Number(start)));
+            };
+            let end = self.pattern_number()?;
+            return Ok(Pattern::Range {
+                start,
+                end,
+                inclusive,
+            });
+        }
+        if self.match_token(&[TokenType::String]) {
+            return Ok(Pattern::Literal(self.previous().literal.unwrap()));
+        }
+        if self.match_token(&[TokenType::True, TokenType::False, TokenType::Nil]) {
+            let value = match self.previous().typ {
+                TokenType::True => LiteralValue::Boolean(true),
+                TokenType::False => LiteralValue::Boolean(false),
+                _ => LiteralValue::Nil,
+            };
+            return Ok(Pattern::Literal(value));
+        }
+        Err(ParseError {
+            token: self.peek().clone(),
+            message: format!("Expect a pattern, got '{}'.", self.peek().lexeme),
+        })
+    }
+
+    fn pattern_number(&mut self) -> Result<f64, ParseError> {
+        let negative = self.match_token(&[TokenType::Minus]);
+        let token = self.consume(TokenType::Number, "Expect number in pattern.")?;
+        match token.literal {
+            Some(LiteralValue::Number(n)) if negative => Ok(-n),
+            Some(LiteralValue::Number(n)) => Ok(n),
+            _ => unreachable!("number tokens always carry a number literal"),
+        }
+    }
+
+    fn import_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous();
+        let path = self.consume(TokenType::String, "Expect module path after 'import'.")?;
+        let Some(LiteralValue::String(path)) = path.literal else {
+            unreachable!("string tokens always carry a string literal")
+        };
+        self.consume(TokenType::As, "Expect 'as' after module path.")?;
+        let name = self.consume(TokenType::Identifier, "Expect module name after 'as'.")?;
+        self.consume(TokenType::SemiColon, "Expect ';' after import.")?;
+        Ok(Stmt::Import(Import {
+            keyword,
+            path,
+            name,
+        }))
+    }
+
+    fn export_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous();
+        let exportable = self.check(&TokenType::Var)
+            || self.check(&TokenType::Const)
+            || self.check(&TokenType::Class)
+            || (self.check(&TokenType::Fun) && !self.check_next(&TokenType::LeftParen));
+        if !exportable {
+            return Err(ParseError {
+                token: self.peek().clone(),
+                message: "Expect 'var', 'const', 'fun' or 'class' after 'export'.".to_string(),
+            });
+        }
+        let declaration = Box::new(self.declaration()?);
+        Ok(Stmt::Export(Export {
+            keyword,
+            declaration,
+        }))
+    }
+
+    fn throw_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous();
+        let value = self.expression()?;
+        self.consume(TokenType::SemiColon, "Expect ';' after thrown value.")?;
+        Ok(Stmt::Throw(Throw { keyword, value }))
+    }
+
+    fn try_statement(&mut self) -> Result<Stmt, ParseError> {
+        self.consume(TokenType::LeftBrace, "Expect '{' after 'try'.")?;
+        let body = self.block()?;
+
+        let catch = if self.match_token(&[TokenType::Catch]) {
+            self.consume(TokenType::LeftParen, "Expect '(' after 'catch'.")?;
+            let name = self.consume(TokenType::Identifier, "Expect exception variable name.")?;
+            self.consume(
+                TokenType::RightParen,
+                "Expect ')' after exception variable.",
+            )?;
+            self.consume(TokenType::LeftBrace, "Expect '{' before catch body.")?;
+            Some(Catch {
+                name,
+                body: self.block()?,
+            })
+        } else {
+            None
+        };
+
+        let finally = if self.match_token(&[TokenType::Finally]) {
+            self.consume(TokenType::LeftBrace, "Expect '{' after 'finally'.")?;
+            Some(self.block()?)
+        } else {
+            None
+        };
+
+        if catch.is_none() && finally.is_none() {
+            return Err(ParseError {
+                token: self.peek().clone(),
+                message: "Expect 'catch' or 'finally' after try block.".to_string(),
+            });
+        }
+        Ok(Stmt::Try(Try {
+            body,
+            catch,
+            finally,
+        }))
+    }
+
+    fn return_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous();
+        let value = if self.check(&TokenType::SemiColon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(TokenType::SemiColon, "Expect ';' after return value.")?;
+        Ok(Stmt::Return(Return { keyword, value }))
+    }
+
+    /// Parse the rest of a `var` or `const` declaration; the keyword has been consumed.
     fn var_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let constant = self.previous().typ == TokenType::Const;
+        if self.match_token(&[TokenType::LeftParen]) {
+            return self.var_destructure(constant);
+        }
         let name = self.consume(TokenType::Identifier, "Expect variable name.")?;
         let initializer = if self.match_token(&[TokenType::Equal]) {
             Some(Box::new(self.expression()?))
+        } else if constant {
+            return Err(ParseError {
+                token: self.peek().clone(),
+                message: format!("Expect initializer for constant '{}'.", name.lexeme),
+            });
         } else {
             None
         };
@@ -189,9 +1133,64 @@ impl Parser {
         Ok(Stmt::Variable(VarAssignment {
             token: name,
             initializer,
+            constant,
+        }))
+    }
+
+    /// Parse `(a, b) = value;` after `var` or `const`; the '(' has already been consumed.
+    fn var_destructure(&mut self, constant: bool) -> Result<Stmt, ParseError> {
+        let target = self.target()?;
+        self.consume(TokenType::Equal, "Expect '=' after destructuring pattern.")?;
+        let initializer = Box::new(self.expression()?);
+        self.consume(
+            TokenType::SemiColon,
+            "Expect ';' after variable declaration.",
+        )?;
+        Ok(Stmt::Destructure(VarDestructure {
+            target,
+            initializer,
+            constant,
         }))
     }
 
+    /// Parse a parenthesized list of names and nested lists; the '(' has been consumed.
+    fn target(&mut self) -> Result<Target, ParseError> {
+        let paren = self.previous();
+        let mut elements = Vec::new();
+        loop {
+            if self.match_token(&[TokenType::LeftParen]) {
+                elements.push(self.target()?);
+            } else {
+                let name = self.consume(
+                    TokenType::Identifier,
+                    "Expect variable name in destructuring pattern.",
+                )?;
+                elements.push(Target::Variable {
+                    id: next_expr_id(),
+                    name,
+                });
+            }
+            if !self.match_token(&[TokenType::Comma]) {
+                break;
+            }
+        }
+        self.consume(
+            TokenType::RightParen,
+            "Expect ')' after destructuring pattern.",
+        )?;
+        Ok(Target::Tuple { paren, elements })
+    }
+
+    /// Parse the declarations of a block; the opening '{' has already been consumed.
+    fn block(&mut self) -> Result<Vec<Stmt>, ParseError> {
+        let mut statements = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            statements.push(self.declaration()?);
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after block.")?;
+        Ok(statements)
+    }
+
     fn expression_statement(&mut self) -> Result<Stmt, ParseError> {
         let expr = self.expression()?;
         self.consume(TokenType::SemiColon, "Expect ';' after expression.")?;
@@ -222,6 +1221,12 @@ impl Parser {
         }
     }
 
+    fn check_next(&self, token_type: &TokenType) -> bool {
+        self.tokens
+            .get(self.current + 1)
+            .is_some_and(|token| token.typ == *token_type)
+    }
+
     fn advance(&mut self) -> Token {
         if !self.is_at_end() {
             self.current += 1;
@@ -246,25 +1251,157 @@ impl Parser {
     }
 
     fn assignment(&mut self) -> Result<Expr, ParseError> {
-        let expr = self.equality()?;
+        let expr = self.conditional()?;
 
         if self.match_token(&[TokenType::Equal]) {
             let equals = self.previous();
             let value = Box::new(self.assignment()?);
-            if let Expr::Variable { token } = expr {
-                return Ok(Expr::Assign { token, value });
-            } else {
-                let lexeme = equals.lexeme.clone();
-                return Err(ParseError {
-                    token: equals,
-                    message: format!(
-                        "Invalid assignment target expected an identifier found '{}'",
-                        &lexeme
-                    ),
+            if let Expr::Variable { token, .. } = expr {
+                return Ok(Expr::Assign {
+                    id: next_expr_id(),
+                    token,
+                    value,
                 });
+            } else if let Expr::Get(get) = expr {
+                return Ok(Expr::Set(Set {
+                    object: get.object,
+                    name: get.name,
+                    value,
+                }));
+            } else if let Expr::Index(index) = expr {
+                return Ok(Expr::IndexSet(IndexSet {
+                    object: index.object,
+                    bracket: index.bracket,
+                    index: index.index,
+                    value,
+                }));
+            } else if let Expr::Tuple(_) = expr {
+                return Ok(Expr::Destructure(Destructure {
+                    target: Self::assignment_target(expr, &equals)?,
+                    value,
+                }));
+            } else {
+                return Err(Self::invalid_assignment_target(equals));
             }
         }
 
+        if self.match_token(&[
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual,
+        ]) {
+            let operator = self.previous();
+            let value = self.assignment()?;
+            return Self::compound(expr, operator, value, false);
+        }
+
+        Ok(expr)
+    }
+
+    /// Turn the tuple on the left of `=` back into the names it assigns.
+    fn assignment_target(expr: Expr, equals: &Token) -> Result<Target, ParseError> {
+        match expr {
+            Expr::Variable { id, token } => Ok(Target::Variable { id, name: token }),
+            Expr::Tuple(tuple) => Ok(Target::Tuple {
+                paren: tuple.paren,
+                elements: tuple
+                    .elements
+                    .into_iter()
+                    .map(|element| Self::assignment_target(element, equals))
+                    .collect::<Result<_, _>>()?,
+            }),
+            _ => Err(Self::invalid_assignment_target(equals.clone())),
+        }
+    }
+
+    fn invalid_assignment_target(equals: Token) -> ParseError {
+        let lexeme = equals.lexeme.clone();
+        ParseError {
+            token: equals,
+            message: format!(
+                "Invalid assignment target expected an identifier found '{}'",
+                &lexeme
+            ),
+        }
+    }
+
+    /// Build a compound assignment or increment, checking that `target` can be assigned to.
+    fn compound(
+        target: Expr,
+        operator: Token,
+        value: Expr,
+        postfix: bool,
+    ) -> Result<Expr, ParseError> {
+        if !matches!(
+            target,
+            Expr::Variable { .. } | Expr::Get(_) | Expr::Index(_)
+        ) {
+            return Err(Self::invalid_assignment_target(operator));
+        }
+        let typ = match operator.typ {
+            TokenType::PlusEqual | TokenType::PlusPlus => TokenType::Plus,
+            TokenType::MinusEqual | TokenType::MinusMinus => TokenType::Minus,
+            TokenType::StarEqual => TokenType::Star,
+            _ => TokenType::Slash,
+        };
+        Ok(Expr::Compound(Compound {
+            target: Box::new(target),
+            operator: Token { typ, ..operator },
+            value: Box::new(value),
+            postfix,
+        }))
+    }
+
+    fn one() -> Expr {
+        Expr::Literal(Literal {
+            value: LiteralValue::Number(1.0),
+        })
+    }
+
+    fn conditional(&mut self) -> Result<Expr, ParseError> {
+        let condition = self.or()?;
+        if self.match_token(&[TokenType::Question]) {
+            let then_branch = self.expression()?;
+            self.consume(
+                TokenType::Colon,
+                "Expect ':' after then branch of conditional expression.",
+            )?;
+            let else_branch = self.conditional()?;
+            return Ok(Expr::Conditional(Conditional {
+                condition: Box::new(condition),
+                then_branch: Box::new(then_branch),
+                else_branch: Box::new(else_branch),
+            }));
+        }
+        Ok(condition)
+    }
+
+    fn or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.and()?;
+        while self.match_token(&[TokenType::Or]) {
+            let operator = self.previous();
+            let right = self.and()?;
+            expr = Expr::Logical(Logical {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            });
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.equality()?;
+        while self.match_token(&[TokenType::And]) {
+            let operator = self.previous();
+            let right = self.equality()?;
+            expr = Expr::Logical(Logical {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            });
+        }
         Ok(expr)
     }
 
@@ -317,7 +1454,12 @@ impl Parser {
 
     fn factor(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.unary()?;
-        while self.match_token(&[TokenType::Slash, TokenType::Star]) {
+        while self.match_token(&[
+            TokenType::Slash,
+            TokenType::Star,
+            TokenType::Percent,
+            TokenType::Div,
+        ]) {
             let operator = self.previous();
             let right = self.unary()?;
             expr = Expr::Binary(Binary {
@@ -338,7 +1480,96 @@ impl Parser {
                 right: Box::new(right),
             }));
         }
-        self.primary()
+        self.power()
+    }
+
+    /// `**` binds tighter than a unary operator on its left, so `-2 ** 2` is `-(2 ** 2)`.
+    /// Its right operand is a unary expression, which makes it right-associative.
+    fn power(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.prefix()?;
+        if self.match_token(&[TokenType::StarStar]) {
+            let operator = self.previous();
+            let right = self.unary()?;
+            return Ok(Expr::Binary(Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            }));
+        }
+        Ok(expr)
+    }
+
+    /// A prefix `++` or `--` only takes the assignable expression after it, so `++x ** 2`
+    /// is `(++x) ** 2`.
+    fn prefix(&mut self) -> Result<Expr, ParseError> {
+        if self.match_token(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous();
+            let target = self.call()?;
+            return Self::compound(target, operator, Self::one(), false);
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.call()?;
+        if self.match_token(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous();
+            return Self::compound(expr, operator, Self::one(), true);
+        }
+        Ok(expr)
+    }
+
+    fn call(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.match_token(&[TokenType::LeftParen]) {
+                expr = self.finish_call(expr)?;
+            } else if self.match_token(&[TokenType::Dot]) {
+                let name =
+                    self.consume(TokenType::Identifier, "Expect property name after '.'.")?;
+                expr = Expr::Get(Get {
+                    object: Box::new(expr),
+                    name,
+                });
+            } else if self.match_token(&[TokenType::LeftBracket]) {
+                let bracket = self.previous();
+                let index = self.expression()?;
+                self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
+                expr = Expr::Index(Index {
+                    object: Box::new(expr),
+                    bracket,
+                    index: Box::new(index),
+                });
+            } else {
+                break;
+            }
+        }
+        Ok(expr)
+    }
+
+    fn finish_call(&mut self, callee: Expr) -> Result<Expr, ParseError> {
+        let mut arguments = Vec::new();
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                if arguments.len() >= MAX_ARGUMENTS {
+                    return Err(ParseError {
+                        token: self.peek().clone(),
+                        message: format!("Can't have more than {} arguments.", MAX_ARGUMENTS),
+                    });
+                }
+                arguments.push(self.expression()?);
+                if !self.match_token(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        let paren = self.consume(TokenType::RightParen, "Expect ')' after arguments.")?;
+
+        Ok(Expr::Call(Call {
+            callee: Box::new(callee),
+            paren,
+            arguments,
+        }))
     }
 
     fn primary(&mut self) -> Result<Expr, ParseError> {
@@ -366,20 +1597,95 @@ impl Parser {
             }));
         }
 
+        if self.match_token(&[TokenType::Interpolation]) {
+            return self.interpolation();
+        }
+
         if self.match_token(&[TokenType::Number]) {
             return Ok(Expr::Literal(Literal {
                 value: self.previous().literal.clone().unwrap(),
             }));
         }
 
+        if self.match_token(&[TokenType::This]) {
+            return Ok(Expr::This {
+                id: next_expr_id(),
+                keyword: self.previous(),
+            });
+        }
+
+        if self.match_token(&[TokenType::Super]) {
+            let keyword = self.previous();
+            self.consume(TokenType::Dot, "Expect '.' after 'super'.")?;
+            let method = self.consume(TokenType::Identifier, "Expect superclass method name.")?;
+            return Ok(Expr::Super(Super {
+                id: next_expr_id(),
+                keyword,
+                method,
+            }));
+        }
+
         if self.match_token(&[TokenType::Identifier]) {
             return Ok(Expr::Variable {
+                id: next_expr_id(),
                 token: self.previous(),
             });
         }
 
+        if self.match_token(&[TokenType::LeftBracket]) {
+            let mut elements = Vec::new();
+            if !self.check(&TokenType::RightBracket) {
+                loop {
+                    elements.push(self.expression()?);
+                    if !self.match_token(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightBracket, "Expect ']' after list elements.")?;
+            return Ok(Expr::List(List { elements }));
+        }
+
+        if self.match_token(&[TokenType::LeftBrace]) {
+            let brace = self.previous();
+            let mut entries = Vec::new();
+            if !self.check(&TokenType::RightBrace) {
+                loop {
+                    let key = self.expression()?;
+                    self.consume(TokenType::Colon, "Expect ':' after map key.")?;
+                    let value = self.expression()?;
+                    entries.push((key, value));
+                    if !self.match_token(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightBrace, "Expect '}' after map entries.")?;
+            return Ok(Expr::Map(Map { brace, entries }));
+        }
+
+        if self.match_token(&[TokenType::Fun]) {
+            return self.lambda();
+        }
+
+        if self.check(&TokenType::LeftParen) && self.is_arrow_function() {
+            return self.arrow_function();
+        }
+
         if self.match_token(&[TokenType::LeftParen]) {
+            let paren = self.previous();
             let expr = self.expression()?;
+            if self.match_token(&[TokenType::Comma]) {
+                let mut elements = vec![expr];
+                loop {
+                    elements.push(self.expression()?);
+                    if !self.match_token(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+                self.consume(TokenType::RightParen, "Expect ')' after tuple elements.")?;
+                return Ok(Expr::Tuple(Tuple { paren, elements }));
+            }
             self.consume(TokenType::RightParen, "Expect ')' after expression.")?;
             return Ok(Expr::Grouping(Grouping {
                 expr: Box::new(expr),
@@ -393,11 +1699,38 @@ impl Parser {
         })
     }
 
-    fn consume(
-        &mut self,
-        token_type: TokenType,
-        message: &'static str,
-    ) -> Result<Token, ParseError> {
+    /// Parse the rest of an interpolated string whose first segment was just consumed.
+    fn interpolation(&mut self) -> Result<Expr, ParseError> {
+        let mut parts = Vec::new();
+        loop {
+            Self::push_segment(&mut parts, self.previous());
+            // A segment that resumes the string right away means the hole was empty.
+            let resumes = self.check(&TokenType::String) || self.check(&TokenType::Interpolation);
+            if resumes && self.peek().lexeme.starts_with('}') {
+                return Err(ParseError {
+                    token: self.peek().clone(),
+                    message: "Expect expression in interpolation.".to_string(),
+                });
+            }
+            parts.push(self.expression()?);
+            if !self.match_token(&[TokenType::Interpolation]) {
+                break;
+            }
+        }
+        let end = self.consume(TokenType::String, "Expect end of string interpolation.")?;
+        Self::push_segment(&mut parts, end);
+        Ok(Expr::Interpolation(Interpolation { parts }))
+    }
+
+    fn push_segment(parts: &mut Vec<Expr>, token: Token) {
+        match token.literal {
+            Some(LiteralValue::String(s)) if s.is_empty() => {}
+            Some(value) => parts.push(Expr::Literal(Literal { value })),
+            None => {}
+        }
+    }
+
+    fn consume(&mut self, token_type: TokenType, message: &str) -> Result<Token, ParseError> {
         if self.check(&token_type) {
             Ok(self.advance())
         } else {
@@ -412,6 +1745,7 @@ impl Parser {
 
 // Add unit tests for the parser.
 #[cfg(test)]
+#[allow(clippy::unnecessary_mut_passed)]
 mod tests {
     use super::*;
     use crate::ast_printer::AstPrinter;
@@ -438,6 +1772,23 @@ mod tests {
         expr.accept(&mut printer)
     }
 
+    /// Like `parse_and_print`, but parses a whole program and prints each statement.
+    fn parse_program_and_print(source: &str) -> String {
+        let mut scanner = Scanner::new(source.to_string());
+        let tokens = scanner.scan_tokens();
+        assert!(!scanner.has_error(), "Scanner reported an error.");
+
+        let mut parser = Parser::new(tokens);
+        let stmts = parser.parse().expect("Parser returned an error");
+
+        let printer = AstPrinter::new();
+        stmts
+            .iter()
+            .map(|stmt| stmt.accept(&printer))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
     #[test]
     fn parses_single_number_literal() {
         assert_eq!(parse_and_print("123"), "123");
@@ -479,6 +1830,424 @@ mod tests {
         assert_eq!(parse_and_print("1 == 1"), "(== 1 1)");
     }
 
+    #[test]
+    fn parses_logical_operators_with_and_binding_tighter_than_or() {
+        assert_eq!(parse_and_print("a or b and c"), "(or a (and b c))");
+    }
+
+    #[test]
+    fn parses_logical_below_equality() {
+        assert_eq!(
+            parse_and_print("x != nil and x > 3"),
+            "(and (!= x nil) (> x 3))"
+        );
+    }
+
+    #[test]
+    fn parses_call_expressions() {
+        assert_eq!(parse_and_print("f(1, a + 2)"), "(call f 1 (+ a 2))");
+        assert_eq!(parse_and_print("make()(3)"), "(call (call make) 3)");
+    }
+
+    #[test]
+    fn parses_call_tighter_than_unary() {
+        assert_eq!(parse_and_print("-f(1)"), "(- (call f 1))");
+    }
+
+    #[test]
+    fn parses_function_declaration_with_return() {
+        assert_eq!(
+            parse_program_and_print("fun add(a, b) { return a + b; }"),
+            "(fun add(a b) (return (+ a b)))"
+        );
+    }
+
+    #[test]
+    fn parses_anonymous_functions() {
+        assert_eq!(
+            parse_and_print("fun (a, b) { return a + b; }"),
+            "(lambda (a b) (return (+ a b)))"
+        );
+        assert_eq!(
+            parse_and_print("(a, b) => a + b"),
+            "(lambda (a b) (return (+ a b)))"
+        );
+        assert_eq!(parse_and_print("() => 1"), "(lambda () (return 1))");
+        assert_eq!(parse_and_print("(a) * 2"), "(* (group a) 2)");
+        assert_eq!(
+            parse_and_print("f((x) => x, (y))"),
+            "(call f (lambda (x) (return x)) (group y))"
+        );
+        assert_eq!(
+            parse_program_and_print("fun () {}();"),
+            "(call (lambda ()))"
+        );
+    }
+
+    #[test]
+    fn reports_error_on_missing_parameter_name() {
+        let mut scanner = Scanner::new("fun f(1) {}".to_string());
+        let tokens = scanner.scan_tokens();
+        let mut parser = Parser::new(tokens);
+        assert!(parser.parse().is_err());
+    }
+
+    #[test]
+    fn parses_property_get_and_set() {
+        assert_eq!(parse_and_print("a.b.c"), "(. (. a b) c)");
+        assert_eq!(
+            parse_and_print("a.b(1).c = 2"),
+            "(set (call (. a b) 1) c = 2)"
+        );
+    }
+
+    #[test]
+    fn parses_class_declaration_with_superclass() {
+        assert_eq!(
+            parse_program_and_print(
+                "class B < A { init(x) { this.x = x; } get() { return super.get(); } }"
+            ),
+            "(class B < A (fun init(x) (set this x = x)) (fun get() (return (call (super get)))))"
+        );
+    }
+
+    #[test]
+    fn reports_error_on_super_without_method() {
+        let mut scanner = Scanner::new("super;".to_string());
+        let tokens = scanner.scan_tokens();
+        let mut parser = Parser::new(tokens);
+        assert!(parser.parse().is_err());
+    }
+
+    #[test]
+    fn parses_import_and_export() {
+        assert_eq!(
+            parse_program_and_print("import \"lib/util.lox\" as util; export var x = 1;"),
+            "(import \"lib/util.lox\" as util)\n(export x)"
+        );
+        assert_eq!(
+            parse_program_and_print("export fun f() {}"),
+            "(export (fun f()))"
+        );
+    }
+
+    #[test]
+    fn parses_const_declaration() {
+        assert_eq!(
+            parse_program_and_print("const limit = 10; export const name = \"x\";"),
+            "(const limit)\n(export (const name))"
+        );
+    }
+
+    #[test]
+    fn requires_const_initializer() {
+        let mut scanner = Scanner::new("const limit;".to_string());
+        let err = Parser::new(scanner.scan_tokens()).parse().err().unwrap();
+        assert_eq!(err.message, "Expect initializer for constant 'limit'.");
+    }
+
+    #[test]
+    fn parses_tuples_and_destructuring() {
+        assert_eq!(parse_and_print("(1, a, \"s\")"), "(tuple 1 a s)");
+        assert_eq!(parse_and_print("(1)"), "(group 1)");
+        assert_eq!(
+            parse_program_and_print("var (a, (b, c)) = t; const (x, y) = (1, 2); (a, b) = (b, a);"),
+            "(var (a, (b, c)) = t)\n(const (x, y) = (tuple 1 2))\n(a, b) = (tuple b a)"
+        );
+        assert_eq!(parse_program_and_print("var f = (a, b) => a;"), "f");
+    }
+
+    #[test]
+    fn reports_error_on_invalid_destructuring_target() {
+        for source in [
+            "(a, 1) = t;",
+            "(a, b.c) = t;",
+            "(a, b) += t;",
+            "var (a, 1) = t;",
+            "var (a, b);",
+        ] {
+            let mut scanner = Scanner::new(source.to_string());
+            let mut parser = Parser::new(scanner.scan_tokens());
+            assert!(parser.parse().is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn reports_error_on_invalid_import_or_export() {
+        for source in [
+            "import util;",
+            "import \"util.lox\";",
+            "export print 1;",
+            "export fun () {};",
+        ] {
+            let mut scanner = Scanner::new(source.to_string());
+            let mut parser = Parser::new(scanner.scan_tokens());
+            assert!(parser.parse().is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn parses_throw_and_try_statements() {
+        assert_eq!(
+            parse_program_and_print("try { f(); } catch (e) { print e; } finally { g(); }"),
+            "(try (block (call f)) (catch e print e) (finally (block (call g))))"
+        );
+        assert_eq!(
+            parse_program_and_print("try { throw \"x\"; } finally {}"),
+            "(try (block (throw x)) (finally (block)))"
+        );
+    }
+
+    #[test]
+    fn reports_error_on_try_without_handler() {
+        let mut scanner = Scanner::new("try { f(); } print 1;".to_string());
+        let mut parser = Parser::new(scanner.scan_tokens());
+        let Err(e) = parser.parse() else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(e.message, "Expect 'catch' or 'finally' after try block.");
+    }
+
+    #[test]
+    fn parses_match_statement() {
+        assert_eq!(
+            parse_program_and_print(
+                "match x { 1 | -2 => \"a\", 0..10 if x > 3 => { y = 1; } \"s\" => f(2), _ => { print 3; } }"
+            ),
+            "(match x (1 | -2 => a) (0..10 if (> x 3) => (block y = 1)) (\"s\" => (call f 2)) (_ => (block print 3)))"
+        );
+        assert_eq!(
+            parse_program_and_print("match n { 1..=3 => n, nil | true => 0 }"),
+            "(match n (1..=3 => n) (nil | true => 0))"
+        );
+        assert_eq!(
+            parse_program_and_print(
+                "match v { 1 => r = \"one\", \"a\" | \"b\" => r = \"letter\", _ => r = \"other\", }"
+            ),
+            "(match v (1 => r = one) (\"a\" | \"b\" => r = letter) (_ => r = other))"
+        );
+    }
+
+    #[test]
+    fn warns_about_arms_after_a_wildcard() {
+        let parse = |source: &str| {
+            let mut scanner = Scanner::new(source.to_string());
+            let mut parser = Parser::new(scanner.scan_tokens());
+            assert!(parser.parse().is_ok());
+            parser.warnings().len()
+        };
+        assert_eq!(parse("match x { _ => 1, 2 => 2, 3 => 3 }"), 2);
+        assert_eq!(parse("match x { _ if x => 1, 2 => 2 }"), 0);
+        assert_eq!(parse("match x { 1 => 1, _ => { print 2; } }"), 0);
+    }
+
+    #[test]
+    fn reports_error_on_invalid_pattern() {
+        for source in ["match x { y => 1 }", "match x { 1 => print 1; }"] {
+            let mut scanner = Scanner::new(source.to_string());
+            let mut parser = Parser::new(scanner.scan_tokens());
+            assert!(parser.parse().is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn parses_compound_assignment_and_increments() {
+        assert_eq!(parse_and_print("x += 1"), "(+= x 1)");
+        assert_eq!(parse_and_print("a.b *= c - 1"), "(*= (. a b) (- c 1))");
+        assert_eq!(parse_and_print("xs[i] /= 2"), "(/= (index xs i) 2)");
+        assert_eq!(parse_and_print("x -= y += 2"), "(-= x (+= y 2))");
+        assert_eq!(parse_and_print("++x"), "(++ x)");
+        assert_eq!(parse_and_print("x--"), "(x --)");
+        assert_eq!(parse_and_print("-x++"), "(- (x ++))");
+        assert_eq!(parse_and_print("++x ** 2"), "(** (++ x) 2)");
+        assert_eq!(parse_and_print("2 ** --x"), "(** 2 (-- x))");
+        assert_eq!(parse_and_print("-++a.b"), "(- (++ (. a b)))");
+    }
+
+    #[test]
+    fn reports_error_on_invalid_compound_target() {
+        for source in ["1 += 2;", "(a) -= 1;", "f()++;", "++3;"] {
+            let mut scanner = Scanner::new(source.to_string());
+            let tokens = scanner.scan_tokens();
+            let mut parser = Parser::new(tokens);
+            let Err(e) = parser.parse() else {
+                panic!("expected a parse error for {}", source);
+            };
+            assert!(
+                e.message.starts_with("Invalid assignment target"),
+                "{}",
+                e.message
+            );
+        }
+    }
+
+    #[test]
+    fn parses_conditional_expressions() {
+        assert_eq!(parse_and_print("a ? b : c"), "(?: a b c)");
+        assert_eq!(parse_and_print("a ? b : c ? d : e"), "(?: a b (?: c d e))");
+        assert_eq!(parse_and_print("a or b ? 1 : 2"), "(?: (or a b) 1 2)");
+        assert_eq!(parse_and_print("x = a ? b : c"), "x = (?: a b c)");
+    }
+
+    #[test]
+    fn reports_error_on_conditional_without_colon() {
+        let mut scanner = Scanner::new("a ? b;".to_string());
+        let tokens = scanner.scan_tokens();
+        let mut parser = Parser::new(tokens);
+        assert!(parser.parse().is_err());
+    }
+
+    #[test]
+    fn parses_modulo_power_and_floor_division() {
+        assert_eq!(parse_and_print("a % b * c"), "(* (% a b) c)");
+        assert_eq!(parse_and_print("a div b + 1"), "(+ (div a b) 1)");
+        assert_eq!(parse_and_print("2 ** 3 ** 2"), "(** 2 (** 3 2))");
+        assert_eq!(parse_and_print("-2 ** 2"), "(- (** 2 2))");
+        assert_eq!(parse_and_print("2 ** -1"), "(** 2 (- 1))");
+        assert_eq!(parse_and_print("2 * x ** 2"), "(* 2 (** x 2))");
+    }
+
+    #[test]
+    fn parses_list_literals_and_indexing() {
+        assert_eq!(parse_and_print("[]"), "(list)");
+        assert_eq!(parse_and_print("[1, a + 2]"), "(list 1 (+ a 2))");
+        assert_eq!(parse_and_print("xs[0][i]"), "(index (index xs 0) i)");
+        assert_eq!(parse_and_print("xs[i] = 3"), "(index-set xs i = 3)");
+    }
+
+    #[test]
+    fn parses_map_literals_in_expression_position() {
+        assert_eq!(parse_and_print("{}"), "(map)");
+        assert_eq!(
+            parse_and_print("{\"a\": 1, b: c + 1}"),
+            "(map (a 1) (b (+ c 1)))"
+        );
+        assert_eq!(
+            parse_program_and_print("m = {1: true};"),
+            "m = (map (1 true))"
+        );
+        assert_eq!(parse_program_and_print("{ print 1; }"), "(block print 1)");
+    }
+
+    #[test]
+    fn reports_error_on_map_entry_without_colon() {
+        let mut scanner = Scanner::new("var m = {\"a\" 1};".to_string());
+        let tokens = scanner.scan_tokens();
+        let mut parser = Parser::new(tokens);
+        assert!(parser.parse().is_err());
+    }
+
+    #[test]
+    fn parses_string_interpolation() {
+        assert_eq!(
+            parse_and_print("\"Hello ${name}, you are ${age + 1}\""),
+            "(interpolate \"Hello \" name \", you are \" (+ age 1))"
+        );
+        assert_eq!(
+            parse_and_print("\"${ {\"k\": \"${x}\"}[\"k\"] }!\""),
+            "(interpolate (index (map (k (interpolate x))) k) \"!\")"
+        );
+    }
+
+    #[test]
+    fn reports_error_on_unterminated_interpolation() {
+        let mut scanner = Scanner::new("\"a ${b".to_string());
+        let tokens = scanner.scan_tokens();
+        assert!(scanner.has_error());
+        let mut parser = Parser::new(tokens);
+        assert!(parser.parse().is_err());
+    }
+
+    #[test]
+    fn reports_error_on_empty_interpolation() {
+        for source in ["\"a ${} b\";", "\"${x}${}\";"] {
+            let mut scanner = Scanner::new(source.to_string());
+            let err = Parser::new(scanner.scan_tokens()).parse().err().unwrap();
+            assert_eq!(
+                err.message, "Expect expression in interpolation.",
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn reports_error_on_unterminated_index() {
+        let mut scanner = Scanner::new("xs[0;".to_string());
+        let tokens = scanner.scan_tokens();
+        let mut parser = Parser::new(tokens);
+        assert!(parser.parse().is_err());
+    }
+
+    #[test]
+    fn parses_block_statement() {
+        assert_eq!(
+            parse_program_and_print("{ var a = 1; print a; }"),
+            "(block a print a)"
+        );
+    }
+
+    #[test]
+    fn parses_if_else_statement() {
+        assert_eq!(
+            parse_program_and_print("if (a < 1) print 1; else print 2;"),
+            "(if (< a 1) print 1 print 2)"
+        );
+    }
+
+    #[test]
+    fn parses_if_without_else() {
+        assert_eq!(parse_program_and_print("if (a) print 1;"), "(if a print 1)");
+    }
+
+    #[test]
+    fn dangling_else_binds_to_nearest_if() {
+        assert_eq!(
+            parse_program_and_print("if (a) if (b) print 1; else print 2;"),
+            "(if a (if b print 1 print 2))"
+        );
+    }
+
+    #[test]
+    fn parses_while_statement() {
+        assert_eq!(
+            parse_program_and_print("while (i < 3) i = i + 1;"),
+            "(while (< i 3) i = (+ i 1))"
+        );
+    }
+
+    #[test]
+    fn lowers_for_statement_to_while() {
+        assert_eq!(
+            parse_program_and_print("for (var i = 0; i < 3; i = i + 1) print i;"),
+            "(block i (while (< i 3) print i i = (+ i 1)))"
+        );
+    }
+
+    #[test]
+    fn lowers_for_statement_without_clauses() {
+        assert_eq!(
+            parse_program_and_print("for (;;) print 1;"),
+            "(while true print 1)"
+        );
+    }
+
+    #[test]
+    fn parses_break_and_continue() {
+        assert_eq!(
+            parse_program_and_print("while (true) { if (a) break; continue; }"),
+            "(while true (block (if a break) continue))"
+        );
+    }
+
+    #[test]
+    fn reports_error_on_if_without_parentheses() {
+        let mut scanner = Scanner::new("if a print 1;".to_string());
+        let tokens = scanner.scan_tokens();
+        let mut parser = Parser::new(tokens);
+        assert!(parser.parse().is_err());
+    }
+
     #[test]
     fn reports_error_on_unterminated_parentheses() {
         // A lone '(' cannot form a valid expression and should result in a ParseError.
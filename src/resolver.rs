@@ -0,0 +1,762 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::rc::Rc;
+
+use crate::parser::{
+    Binary, Call, Class, Compound, Conditional, Destructure, Export, Expr, ExprId, ExprVisitorMut,
+    Function, Get, Grouping, If, Import, Index, IndexSet, Interpolation, List, Literal, Logical,
+    Map, Match, Return, Set, Stmt, StmtVisitorMut, Super, Throw, Try, Tuple, Unary, VarAssignment,
+    VarDestructure, While,
+};
+use crate::token::Token;
+
+#[derive(Debug, Clone)]
+pub struct ResolveError {
+    pub token: Token,
+    pub message: String,
+}
+
+impl Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ResolveError: {} at line {}",
+            self.message, self.token.line
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionType {
+    None,
+    Function,
+    Method,
+    Initializer,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+}
+
+type ResolveResult = Result<(), ResolveError>;
+
+/// Static pass run between parsing and interpreting. It records, for every variable
+/// reference, how many scopes separate it from its binding, and rejects programs that
+/// misuse scopes before any of their code runs.
+pub struct Resolver {
+    /// One map per enclosing local scope; the flag is `true` once the variable's
+    /// initializer has been resolved. Globals are not tracked.
+    scopes: Vec<HashMap<String, bool>>,
+    /// The `const` names of each scope in `scopes`.
+    constants: Vec<HashSet<String>>,
+    /// Every top-level `const` name of the program, wherever it is declared.
+    global_constants: HashSet<String>,
+    locals: HashMap<ExprId, usize>,
+    current_function: FunctionType,
+    current_class: ClassType,
+    /// Number of loops enclosing the current statement within the current function.
+    loop_depth: usize,
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Resolver {
+            scopes: Vec::new(),
+            constants: Vec::new(),
+            global_constants: HashSet::new(),
+            locals: HashMap::new(),
+            current_function: FunctionType::None,
+            current_class: ClassType::None,
+            loop_depth: 0,
+        }
+    }
+
+    /// Resolve a whole program, returning the scope depth of every local variable reference.
+    /// References that are absent from the result are globals.
+    pub fn resolve(mut self, statements: &[Stmt]) -> Result<HashMap<ExprId, usize>, ResolveError> {
+        self.collect_global_constants(statements)?;
+        self.resolve_stmts(statements)?;
+        Ok(self.locals)
+    }
+
+    /// Record the program's top-level constants up front, so an assignment to one is
+    /// rejected even inside a function declared before it. A constant's name can't be
+    /// declared a second time at the top level, so it stays constant for the whole run.
+    fn collect_global_constants(&mut self, statements: &[Stmt]) -> ResolveResult {
+        let mut declared: HashMap<&str, bool> = HashMap::new();
+        for stmt in statements {
+            let (names, constant) = match stmt {
+                Stmt::Export(export) => (export.names(), export.is_constant()),
+                Stmt::Variable(var) => (vec![&var.token], var.constant),
+                Stmt::Destructure(stmt) => (
+                    stmt.target
+                        .variables()
+                        .into_iter()
+                        .map(|(_, name)| name)
+                        .collect(),
+                    stmt.constant,
+                ),
+                Stmt::Function(function) => (vec![&function.name], false),
+                Stmt::Class(class) => (vec![&class.name], false),
+                Stmt::Import(import) => (vec![&import.name], false),
+                _ => continue,
+            };
+            for name in names {
+                if let Some(previous) = declared.insert(&name.lexeme, constant)
+                    && (previous || constant)
+                {
+                    return Err(ResolveError {
+                        token: name.clone(),
+                        message: format!("Can't redeclare constant '{}'.", name.lexeme),
+                    });
+                }
+            }
+        }
+        self.global_constants = declared
+            .into_iter()
+            .filter(|(_, constant)| *constant)
+            .map(|(name, _)| name.to_string())
+            .collect();
+        Ok(())
+    }
+
+    fn resolve_stmts(&mut self, statements: &[Stmt]) -> ResolveResult {
+        statements.iter().try_for_each(|stmt| stmt.accept_mut(self))
+    }
+
+    fn resolve_function(&mut self, function: &Function, typ: FunctionType) -> ResolveResult {
+        let enclosing_function = self.current_function;
+        let enclosing_loop_depth = self.loop_depth;
+        self.current_function = typ;
+        // A loop outside the function can't be the target of a `break` inside it.
+        self.loop_depth = 0;
+
+        self.begin_scope();
+        let result = function
+            .params
+            .iter()
+            .try_for_each(|param| {
+                self.declare(param)?;
+                self.define(&param.lexeme);
+                Ok(())
+            })
+            .and_then(|()| self.resolve_stmts(&function.body));
+        self.end_scope();
+
+        self.current_function = enclosing_function;
+        self.loop_depth = enclosing_loop_depth;
+        result
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+        self.constants.push(HashSet::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+        self.constants.pop();
+    }
+
+    fn declare(&mut self, name: &Token) -> ResolveResult {
+        let Some(scope) = self.scopes.last_mut() else {
+            return Ok(());
+        };
+        if scope.contains_key(&name.lexeme) {
+            return Err(ResolveError {
+                token: name.clone(),
+                message: format!("Already a variable named '{}' in this scope.", name.lexeme),
+            });
+        }
+        scope.insert(name.lexeme.clone(), false);
+        Ok(())
+    }
+
+    fn define(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), true);
+        }
+    }
+
+    fn define_const(&mut self, name: &str) {
+        self.define(name);
+        if let Some(constants) = self.constants.last_mut() {
+            constants.insert(name.to_string());
+        }
+    }
+
+    /// Reject assignments to a name whose binding is known to be a constant. Constants
+    /// from earlier REPL lines or other modules are left for the interpreter to check.
+    fn check_assignable(&self, name: &Token) -> ResolveResult {
+        let constant = match self
+            .scopes
+            .iter()
+            .rposition(|scope| scope.contains_key(&name.lexeme))
+        {
+            Some(i) => self.constants[i].contains(&name.lexeme),
+            None => self.global_constants.contains(&name.lexeme),
+        };
+        if constant {
+            return Err(ResolveError {
+                token: name.clone(),
+                message: format!("Can't assign to constant '{}'.", name.lexeme),
+            });
+        }
+        Ok(())
+    }
+
+    fn check_in_loop(&self, keyword: &Token) -> ResolveResult {
+        if self.loop_depth == 0 {
+            return Err(ResolveError {
+                token: keyword.clone(),
+                message: format!("Can't use '{}' outside of a loop.", keyword.lexeme),
+            });
+        }
+        Ok(())
+    }
+
+    fn resolve_local(&mut self, id: ExprId, name: &str) {
+        if let Some(depth) = self
+            .scopes
+            .iter()
+            .rev()
+            .position(|scope| scope.contains_key(name))
+        {
+            self.locals.insert(id, depth);
+        }
+    }
+}
+
+impl StmtVisitorMut<ResolveResult> for Resolver {
+    fn visit_expr(&mut self, expr: &Expr) -> ResolveResult {
+        expr.accept_mut(self)
+    }
+
+    fn visit_print(&mut self, expr: &Expr) -> ResolveResult {
+        expr.accept_mut(self)
+    }
+
+    fn visit_variable(&mut self, var: &VarAssignment) -> ResolveResult {
+        self.declare(&var.token)?;
+        if let Some(initializer) = &var.initializer {
+            initializer.accept_mut(self)?;
+        }
+        if var.constant {
+            self.define_const(&var.token.lexeme);
+        } else {
+            self.define(&var.token.lexeme);
+        }
+        Ok(())
+    }
+
+    fn visit_var_destructure(&mut self, stmt: &VarDestructure) -> ResolveResult {
+        let variables = stmt.target.variables();
+        let mut seen = HashSet::new();
+        for (_, name) in &variables {
+            if !seen.insert(&name.lexeme) {
+                return Err(ResolveError {
+                    token: (*name).clone(),
+                    message: format!(
+                        "Variable '{}' appears twice in destructuring pattern.",
+                        name.lexeme
+                    ),
+                });
+            }
+            self.declare(name)?;
+        }
+        stmt.initializer.accept_mut(self)?;
+        for (_, name) in variables {
+            if stmt.constant {
+                self.define_const(&name.lexeme);
+            } else {
+                self.define(&name.lexeme);
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_block(&mut self, stmts: &[Stmt]) -> ResolveResult {
+        self.begin_scope();
+        let result = self.resolve_stmts(stmts);
+        self.end_scope();
+        result
+    }
+
+    fn visit_if(&mut self, stmt: &If) -> ResolveResult {
+        stmt.condition.accept_mut(self)?;
+        stmt.then_branch.accept_mut(self)?;
+        if let Some(else_branch) = &stmt.else_branch {
+            else_branch.accept_mut(self)?;
+        }
+        Ok(())
+    }
+
+    fn visit_while(&mut self, stmt: &While) -> ResolveResult {
+        stmt.condition.accept_mut(self)?;
+        self.loop_depth += 1;
+        let result = stmt.body.accept_mut(self);
+        self.loop_depth -= 1;
+        result?;
+        match &stmt.increment {
+            Some(increment) => increment.accept_mut(self),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_function(&mut self, stmt: &Rc<Function>) -> ResolveResult {
+        // Define eagerly so the function can refer to itself recursively.
+        self.declare(&stmt.name)?;
+        self.define(&stmt.name.lexeme);
+        self.resolve_function(stmt, FunctionType::Function)
+    }
+
+    fn visit_break(&mut self, keyword: &Token) -> ResolveResult {
+        self.check_in_loop(keyword)
+    }
+
+    fn visit_continue(&mut self, keyword: &Token) -> ResolveResult {
+        self.check_in_loop(keyword)
+    }
+
+    fn visit_match(&mut self, stmt: &Match) -> ResolveResult {
+        stmt.value.accept_mut(self)?;
+        for arm in &stmt.arms {
+            if let Some(guard) = &arm.guard {
+                guard.accept_mut(self)?;
+            }
+            arm.body.accept_mut(self)?;
+        }
+        Ok(())
+    }
+
+    fn visit_throw(&mut self, stmt: &Throw) -> ResolveResult {
+        stmt.value.accept_mut(self)
+    }
+
+    fn visit_try(&mut self, stmt: &Try) -> ResolveResult {
+        self.visit_block(&stmt.body)?;
+        if let Some(catch) = &stmt.catch {
+            // The exception variable lives in the same scope as the catch body.
+            self.begin_scope();
+            self.declare(&catch.name)?;
+            self.define(&catch.name.lexeme);
+            let result = self.resolve_stmts(&catch.body);
+            self.end_scope();
+            result?;
+        }
+        if let Some(finally) = &stmt.finally {
+            self.visit_block(finally)?;
+        }
+        Ok(())
+    }
+
+    fn visit_import(&mut self, stmt: &Import) -> ResolveResult {
+        self.declare(&stmt.name)?;
+        self.define(&stmt.name.lexeme);
+        Ok(())
+    }
+
+    fn visit_export(&mut self, stmt: &Export) -> ResolveResult {
+        if !self.scopes.is_empty() {
+            return Err(ResolveError {
+                token: stmt.keyword.clone(),
+                message: "Can only export top-level declarations.".to_string(),
+            });
+        }
+        stmt.declaration.accept_mut(self)
+    }
+
+    fn visit_return(&mut self, stmt: &Return) -> ResolveResult {
+        if self.current_function == FunctionType::None {
+            return Err(ResolveError {
+                token: stmt.keyword.clone(),
+                message: "Can't return from top-level code.".to_string(),
+            });
+        }
+        if let Some(value) = &stmt.value {
+            if self.current_function == FunctionType::Initializer {
+                return Err(ResolveError {
+                    token: stmt.keyword.clone(),
+                    message: "Can't return a value from an initializer.".to_string(),
+                });
+            }
+            value.accept_mut(self)?;
+        }
+        Ok(())
+    }
+
+    fn visit_class(&mut self, stmt: &Class) -> ResolveResult {
+        let enclosing_class = self.current_class;
+        self.current_class = ClassType::Class;
+
+        self.declare(&stmt.name)?;
+        self.define(&stmt.name.lexeme);
+
+        if let Some(superclass) = &stmt.superclass {
+            if let Expr::Variable { token, .. } = superclass
+                && token.lexeme == stmt.name.lexeme
+            {
+                return Err(ResolveError {
+                    token: token.clone(),
+                    message: "A class can't inherit from itself.".to_string(),
+                });
+            }
+            self.current_class = ClassType::Subclass;
+            superclass.accept_mut(self)?;
+
+            self.begin_scope();
+            self.define("super");
+        }
+
+        self.begin_scope();
+        self.define("this");
+
+        let result = stmt.methods.iter().try_for_each(|method| {
+            let typ = if method.name.lexeme == "init" {
+                FunctionType::Initializer
+            } else {
+                FunctionType::Method
+            };
+            self.resolve_function(method, typ)
+        });
+
+        self.end_scope();
+        if stmt.superclass.is_some() {
+            self.end_scope();
+        }
+
+        self.current_class = enclosing_class;
+        result
+    }
+}
+
+impl ExprVisitorMut<ResolveResult> for Resolver {
+    fn visit_binary(&mut self, expr: &Binary) -> ResolveResult {
+        expr.left.accept_mut(self)?;
+        expr.right.accept_mut(self)
+    }
+
+    fn visit_logical(&mut self, expr: &Logical) -> ResolveResult {
+        expr.left.accept_mut(self)?;
+        expr.right.accept_mut(self)
+    }
+
+    fn visit_conditional(&mut self, expr: &Conditional) -> ResolveResult {
+        expr.condition.accept_mut(self)?;
+        expr.then_branch.accept_mut(self)?;
+        expr.else_branch.accept_mut(self)
+    }
+
+    fn visit_unary(&mut self, expr: &Unary) -> ResolveResult {
+        expr.right.accept_mut(self)
+    }
+
+    fn visit_lambda(&mut self, function: &Rc<Function>) -> ResolveResult {
+        self.resolve_function(function, FunctionType::Function)
+    }
+
+    fn visit_call(&mut self, expr: &Call) -> ResolveResult {
+        expr.callee.accept_mut(self)?;
+        expr.arguments
+            .iter()
+            .try_for_each(|argument| argument.accept_mut(self))
+    }
+
+    fn visit_grouping(&mut self, expr: &Grouping) -> ResolveResult {
+        expr.expr.accept_mut(self)
+    }
+
+    fn visit_literal(&mut self, _expr: &Literal) -> ResolveResult {
+        Ok(())
+    }
+
+    fn visit_variable(&mut self, id: ExprId, token: &Token) -> ResolveResult {
+        if let Some(false) = self
+            .scopes
+            .last()
+            .and_then(|scope| scope.get(&token.lexeme))
+        {
+            return Err(ResolveError {
+                token: token.clone(),
+                message: "Can't read local variable in its own initializer.".to_string(),
+            });
+        }
+        self.resolve_local(id, &token.lexeme);
+        Ok(())
+    }
+
+    fn visit_assign(&mut self, id: ExprId, token: &Token, value: &Box<Expr>) -> ResolveResult {
+        self.check_assignable(token)?;
+        value.accept_mut(self)?;
+        self.resolve_local(id, &token.lexeme);
+        Ok(())
+    }
+
+    fn visit_get(&mut self, expr: &Get) -> ResolveResult {
+        expr.object.accept_mut(self)
+    }
+
+    fn visit_set(&mut self, expr: &Set) -> ResolveResult {
+        expr.value.accept_mut(self)?;
+        expr.object.accept_mut(self)
+    }
+
+    fn visit_list(&mut self, expr: &List) -> ResolveResult {
+        expr.elements
+            .iter()
+            .try_for_each(|element| element.accept_mut(self))
+    }
+
+    fn visit_index(&mut self, expr: &Index) -> ResolveResult {
+        expr.object.accept_mut(self)?;
+        expr.index.accept_mut(self)
+    }
+
+    fn visit_index_set(&mut self, expr: &IndexSet) -> ResolveResult {
+        expr.value.accept_mut(self)?;
+        expr.object.accept_mut(self)?;
+        expr.index.accept_mut(self)
+    }
+
+    fn visit_tuple(&mut self, expr: &Tuple) -> ResolveResult {
+        expr.elements
+            .iter()
+            .try_for_each(|element| element.accept_mut(self))
+    }
+
+    fn visit_destructure(&mut self, expr: &Destructure) -> ResolveResult {
+        let variables = expr.target.variables();
+        for (_, name) in &variables {
+            self.check_assignable(name)?;
+        }
+        expr.value.accept_mut(self)?;
+        for (id, name) in variables {
+            self.resolve_local(id, &name.lexeme);
+        }
+        Ok(())
+    }
+
+    fn visit_compound(&mut self, expr: &Compound) -> ResolveResult {
+        if let Expr::Variable { token, .. } = expr.target.as_ref() {
+            self.check_assignable(token)?;
+        }
+        expr.value.accept_mut(self)?;
+        expr.target.accept_mut(self)
+    }
+
+    fn visit_map(&mut self, expr: &Map) -> ResolveResult {
+        for (key, value) in &expr.entries {
+            key.accept_mut(self)?;
+            value.accept_mut(self)?;
+        }
+        Ok(())
+    }
+
+    fn visit_interpolation(&mut self, expr: &Interpolation) -> ResolveResult {
+        expr.parts.iter().try_for_each(|part| part.accept_mut(self))
+    }
+
+    fn visit_this(&mut self, id: ExprId, keyword: &Token) -> ResolveResult {
+        if self.current_class == ClassType::None {
+            return Err(ResolveError {
+                token: keyword.clone(),
+                message: "Can't use 'this' outside of a class.".to_string(),
+            });
+        }
+        self.resolve_local(id, &keyword.lexeme);
+        Ok(())
+    }
+
+    fn visit_super(&mut self, expr: &Super) -> ResolveResult {
+        let message = match self.current_class {
+            ClassType::None => "Can't use 'super' outside of a class.",
+            ClassType::Class => "Can't use 'super' in a class with no superclass.",
+            ClassType::Subclass => {
+                self.resolve_local(expr.id, &expr.keyword.lexeme);
+                return Ok(());
+            }
+        };
+        Err(ResolveError {
+            token: expr.keyword.clone(),
+            message: message.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::scanner::Scanner;
+
+    fn resolve(source: &str) -> Result<HashMap<ExprId, usize>, ResolveError> {
+        let mut scanner = Scanner::new(source.to_string());
+        let tokens = scanner.scan_tokens();
+        assert!(!scanner.has_error(), "Scanner reported an error.");
+        let stmts = Parser::new(tokens)
+            .parse()
+            .expect("Parser returned an error");
+        Resolver::new().resolve(&stmts)
+    }
+
+    fn resolve_err(source: &str) -> String {
+        match resolve(source) {
+            Ok(_) => panic!("Expected a resolve error."),
+            Err(e) => e.message,
+        }
+    }
+
+    #[test]
+    fn records_depths_for_locals_only() {
+        let locals = resolve("var g = 1; { var a = 1; { print a; print g; } }").unwrap();
+        // Only the reference to `a` is local; it lives one scope out.
+        assert_eq!(locals.values().copied().collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn rejects_reading_local_in_its_own_initializer() {
+        assert_eq!(
+            resolve_err("{ var a = a; }"),
+            "Can't read local variable in its own initializer."
+        );
+    }
+
+    #[test]
+    fn allows_global_to_reference_itself_in_initializer() {
+        assert!(resolve("var a = 1; var a = a;").is_ok());
+    }
+
+    #[test]
+    fn rejects_redeclaration_in_same_local_scope() {
+        assert_eq!(
+            resolve_err("fun f() { var a = 1; var a = 2; }"),
+            "Already a variable named 'a' in this scope."
+        );
+    }
+
+    #[test]
+    fn rejects_top_level_return() {
+        assert_eq!(
+            resolve_err("return 1;"),
+            "Can't return from top-level code."
+        );
+    }
+
+    #[test]
+    fn rejects_assignment_to_visible_constants() {
+        for source in [
+            "const a = 1; a = 2;",
+            "{ const a = 1; a = 2; }",
+            "const a = 1; fun f() { a = 2; }",
+            "fun f() { a = 2; } const a = 1;",
+            "{ const a = 1; { a += 1; } }",
+            "const a = 1; a++;",
+            "const a = 1; --a;",
+        ] {
+            assert_eq!(
+                resolve_err(source),
+                "Can't assign to constant 'a'.",
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn allows_assignment_to_shadowing_or_redeclared_variables() {
+        assert!(resolve("const a = 1; { var a = 2; a = 3; }").is_ok());
+        assert!(resolve("{ const a = 1; fun f(a) { a = 2; } }").is_ok());
+    }
+
+    #[test]
+    fn rejects_redeclaring_global_constants() {
+        for source in [
+            "const a = 1; var a = 2; a = 3;",
+            "const a = 1; fun f() { a = 2; } var a = 3; f();",
+            "var a = 1; const a = 2;",
+            "const a = 1; const a = 2;",
+            "const a = 1; fun a() {}",
+            "export const a = 1; class a {}",
+            "const (b, a) = (1, 2); import \"m.lox\" as a;",
+        ] {
+            assert_eq!(
+                resolve_err(source),
+                "Can't redeclare constant 'a'.",
+                "{}",
+                source
+            );
+        }
+        assert!(resolve("var a = 1; var a = 2; { const a = 3; }").is_ok());
+    }
+
+    #[test]
+    fn checks_destructuring_targets() {
+        assert_eq!(
+            resolve_err("var (a, (b, a)) = t;"),
+            "Variable 'a' appears twice in destructuring pattern."
+        );
+        assert_eq!(
+            resolve_err("{ var a = 1; var (a, b) = t; }"),
+            "Already a variable named 'a' in this scope."
+        );
+        assert_eq!(
+            resolve_err("const (a, b) = t; (b, a) = (a, b);"),
+            "Can't assign to constant 'b'."
+        );
+        let locals = resolve("{ var (a, b) = (1, 2); (a, b) = (b, a); }").unwrap();
+        assert_eq!(locals.values().filter(|&&depth| depth == 0).count(), 4);
+    }
+
+    #[test]
+    fn rejects_export_outside_top_level() {
+        assert!(resolve("export var a = 1;").is_ok());
+        assert_eq!(
+            resolve_err("{ export var a = 1; }"),
+            "Can only export top-level declarations."
+        );
+        assert_eq!(
+            resolve_err("fun f() { export fun g() {} }"),
+            "Can only export top-level declarations."
+        );
+    }
+
+    #[test]
+    fn rejects_misplaced_this_and_super() {
+        assert_eq!(
+            resolve_err("print this;"),
+            "Can't use 'this' outside of a class."
+        );
+        assert_eq!(
+            resolve_err("class A { f() { return super.f(); } }"),
+            "Can't use 'super' in a class with no superclass."
+        );
+        assert_eq!(
+            resolve_err("class A < A {}"),
+            "A class can't inherit from itself."
+        );
+    }
+
+    #[test]
+    fn rejects_break_and_continue_outside_loops() {
+        assert_eq!(
+            resolve_err("break;"),
+            "Can't use 'break' outside of a loop."
+        );
+        assert_eq!(
+            resolve_err("while (true) { fun f() { continue; } }"),
+            "Can't use 'continue' outside of a loop."
+        );
+        assert!(resolve("for (;;) { { break; } }").is_ok());
+    }
+
+    #[test]
+    fn rejects_returning_a_value_from_initializer() {
+        assert_eq!(
+            resolve_err("class A { init() { return 1; } }"),
+            "Can't return a value from an initializer."
+        );
+    }
+}
@@ -3,23 +3,27 @@ use crate::token::{LiteralValue, Token, TokenType};
 
 #[derive(Debug)]
 pub struct Scanner {
-    source: String,
+    /// The source as chars, so `start` and `current` count characters rather than bytes.
+    source: Vec<char>,
     tokens: Vec<Token>,
     start: usize,
     current: usize,
     line: usize,
     has_error: bool,
+    /// Brace depth inside each `${ ... }` hole we are currently scanning, innermost last.
+    interpolations: Vec<usize>,
 }
 
 impl Scanner {
     pub fn new(source: String) -> Self {
         Scanner {
-            source,
+            source: source.chars().collect(),
             tokens: Vec::new(),
             start: 0,
             current: 0,
             line: 1,
             has_error: false,
+            interpolations: Vec::new(),
         }
     }
 
@@ -28,6 +32,9 @@ impl Scanner {
             self.start = self.current;
             self.scan_token();
         }
+        if !self.interpolations.is_empty() {
+            self.error("Unterminated string interpolation.");
+        }
         // Final EOF token.
         self.tokens.push(Token::simple(TokenType::Eof, "", self.line));
         self.tokens.clone()
@@ -46,14 +53,73 @@ impl Scanner {
         match c {
             '(' => self.add_simple(TokenType::LeftParen),
             ')' => self.add_simple(TokenType::RightParen),
-            '{' => self.add_simple(TokenType::LeftBrace),
-            '}' => self.add_simple(TokenType::RightBrace),
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.add_simple(TokenType::LeftBrace);
+            }
+            '}' => match self.interpolations.last_mut() {
+                // This brace closes a `${` hole, so the string picks up where it left off.
+                Some(0) => {
+                    self.interpolations.pop();
+                    self.string();
+                }
+                Some(depth) => {
+                    *depth -= 1;
+                    self.add_simple(TokenType::RightBrace);
+                }
+                None => self.add_simple(TokenType::RightBrace),
+            },
+            '[' => self.add_simple(TokenType::LeftBracket),
+            ']' => self.add_simple(TokenType::RightBracket),
             ',' => self.add_simple(TokenType::Comma),
-            '.' => self.add_simple(TokenType::Dot),
-            '-' => self.add_simple(TokenType::Minus),
-            '+' => self.add_simple(TokenType::Plus),
+            ':' => self.add_simple(TokenType::Colon),
+            '?' => self.add_simple(TokenType::Question),
+            '|' => self.add_simple(TokenType::Pipe),
+            '.' => {
+                if self.match_char('.') {
+                    if self.match_char('=') {
+                        self.add_simple(TokenType::DotDotEqual);
+                    } else {
+                        self.add_simple(TokenType::DotDot);
+                    }
+                } else if self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    self.error("Numbers can't start with '.'; add a leading zero, as in '0.5'.");
+                    self.digits(10);
+                } else {
+                    self.add_simple(TokenType::Dot);
+                }
+            }
+            '-' => {
+                if self.match_char('=') {
+                    self.add_simple(TokenType::MinusEqual);
+                } else if self.match_char('-') {
+                    self.add_simple(TokenType::MinusMinus);
+                } else {
+                    self.add_simple(TokenType::Minus);
+                }
+            }
+            '+' => {
+                if self.match_char('=') {
+                    self.add_simple(TokenType::PlusEqual);
+                } else if self.match_char('+') {
+                    self.add_simple(TokenType::PlusPlus);
+                } else {
+                    self.add_simple(TokenType::Plus);
+                }
+            }
             ';' => self.add_simple(TokenType::SemiColon),
-            '*' => self.add_simple(TokenType::Star),
+            '%' => self.add_simple(TokenType::Percent),
+            '*' => {
+                if self.match_char('*') {
+                    self.add_simple(TokenType::StarStar);
+                } else if self.match_char('=') {
+                    self.add_simple(TokenType::StarEqual);
+                } else {
+                    self.add_simple(TokenType::Star);
+                }
+            }
             '!' => {
                 if self.match_char('=') {
                     self.add_simple(TokenType::BangEqual);
@@ -64,6 +130,8 @@ impl Scanner {
             '=' => {
                 if self.match_char('=') {
                     self.add_simple(TokenType::EqualEqual);
+                } else if self.match_char('>') {
+                    self.add_simple(TokenType::Arrow);
                 } else {
                     self.add_simple(TokenType::Equal);
                 }
@@ -88,6 +156,10 @@ impl Scanner {
                     while self.peek() != Some('\n') && !self.is_at_end() {
                         self.advance();
                     }
+                } else if self.match_char('*') {
+                    self.block_comment();
+                } else if self.match_char('=') {
+                    self.add_simple(TokenType::SlashEqual);
                 } else {
                     self.add_simple(TokenType::Slash);
                 }
@@ -98,8 +170,11 @@ impl Scanner {
             // string literal
             '"' => self.string(),
             _ => {
-                if c.is_ascii_digit() {
-                    self.number();
+                if c == 'r' && self.peek() == Some('"') {
+                    self.advance();
+                    self.raw_string();
+                } else if c.is_ascii_digit() {
+                    self.number(c);
                 } else if Self::is_alpha(c) {
                     self.identifier();
                 } else {
@@ -110,18 +185,27 @@ impl Scanner {
     }
 
     fn advance(&mut self) -> Option<char> {
-        let ch = self.source.chars().nth(self.current);
+        let ch = self.char_at(self.current);
         self.current += 1;
         ch
     }
 
+    fn char_at(&self, index: usize) -> Option<char> {
+        self.source.get(index).copied()
+    }
+
+    /// The text of the token being scanned.
+    fn lexeme(&self) -> String {
+        self.source[self.start..self.current].iter().collect()
+    }
+
     fn add_simple(&mut self, typ: TokenType) {
-        let lexeme = self.source[self.start..self.current].to_string();
+        let lexeme = self.lexeme();
         self.tokens.push(Token::simple(typ, &lexeme, self.line));
     }
 
     fn add_literal(&mut self, typ: TokenType, literal: LiteralValue) {
-        let lexeme = self.source[self.start..self.current].to_string();
+        let lexeme = self.lexeme();
         self.tokens
             .push(Token::with_literal(typ, &lexeme, literal, self.line));
     }
@@ -136,18 +220,137 @@ impl Scanner {
     }
 
     fn peek(&self) -> Option<char> {
-        self.source.chars().nth(self.current)
+        self.char_at(self.current)
     }
 
     fn peek_next(&self) -> Option<char> {
-        self.source.chars().nth(self.current + 1)
+        self.char_at(self.current + 1)
     }
 
+    /// Skip a `/* ... */` comment whose opening `/*` was just consumed. Comments nest, so
+    /// every `/*` inside needs its own `*/`.
+    fn block_comment(&mut self) {
+        let opened_on = self.line;
+        let mut depth = 1;
+        while depth > 0 {
+            match self.advance() {
+                None => {
+                    self.error(&format!(
+                        "Unterminated block comment starting on line {}.",
+                        opened_on
+                    ));
+                    return;
+                }
+                Some('\n') => self.line += 1,
+                Some('/') if self.match_char('*') => depth += 1,
+                Some('*') if self.match_char('/') => depth -= 1,
+                Some(_) => {}
+            }
+        }
+    }
+
+    /// Scan a string body up to the closing quote, or up to a `${` which ends this segment
+    /// as an `Interpolation` token and hands the hole back to `scan_token`.
     fn string(&mut self) {
-        while self.peek().is_some() && self.peek() != Some('"') {
-            if self.peek() == Some('\n') {
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                None => {
+                    self.error("Unterminated string.");
+                    return;
+                }
+                Some('"') => break,
+                Some('$') if self.peek_next() == Some('{') => {
+                    self.advance();
+                    self.advance();
+                    self.interpolations.push(0);
+                    self.add_literal(TokenType::Interpolation, LiteralValue::String(value));
+                    return;
+                }
+                Some('\\') => {
+                    self.advance();
+                    if let Some(c) = self.escape() {
+                        value.push(c);
+                    }
+                }
+                Some(c) => {
+                    if c == '\n' {
+                        self.line += 1;
+                    }
+                    value.push(c);
+                    self.advance();
+                }
+            }
+        }
+
+        // closing quote
+        self.advance();
+
+        self.add_literal(TokenType::String, LiteralValue::String(value));
+    }
+
+    /// Decode the escape sequence following a backslash. Reports a scan error and returns
+    /// `None` if it isn't one we understand.
+    fn escape(&mut self) -> Option<char> {
+        let c = match self.peek() {
+            Some(c) if c != '\n' => c,
+            _ => {
+                self.error("Unterminated escape sequence.");
+                return None;
+            }
+        };
+        self.advance();
+        match c {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            '0' => Some('\0'),
+            '"' | '\\' | '$' => Some(c),
+            'u' => self.unicode_escape(),
+            _ => {
+                self.error(&format!("Invalid escape sequence '\\{}'.", c));
+                None
+            }
+        }
+    }
+
+    /// Decode the `{XXXX}` part of a `\u{XXXX}` escape: one to six hex digits naming a
+    /// Unicode scalar value.
+    fn unicode_escape(&mut self) -> Option<char> {
+        if !self.match_char('{') {
+            self.error("Expect '{' after '\\u'.");
+            return None;
+        }
+        let mut digits = String::new();
+        while let Some(c) = self.peek().filter(|c| c.is_ascii_hexdigit()) {
+            digits.push(c);
+            self.advance();
+        }
+        if !self.match_char('}') {
+            self.error("Expect '}' after Unicode escape digits.");
+            return None;
+        }
+        let decoded = match digits.len() {
+            1..=6 => u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32),
+            _ => None,
+        };
+        if decoded.is_none() {
+            self.error(&format!("Invalid Unicode escape '\\u{{{}}}'.", digits));
+        }
+        decoded
+    }
+
+    /// Scan a raw string `r"..."`: backslashes and `${` are kept as written.
+    fn raw_string(&mut self) {
+        let mut value = String::new();
+        while let Some(c) = self.peek() {
+            if c == '"' {
+                break;
+            }
+            if c == '\n' {
                 self.line += 1;
             }
+            value.push(c);
             self.advance();
         }
 
@@ -159,27 +362,110 @@ impl Scanner {
         // closing quote
         self.advance();
 
-        let value = self.source[self.start + 1..self.current - 1].to_string();
         self.add_literal(TokenType::String, LiteralValue::String(value));
     }
 
-    fn number(&mut self) {
-        while self.peek().map(|c| c.is_ascii_digit()).unwrap_or(false) {
-            self.advance();
+    /// Scan a number literal whose first digit `first` was just consumed: decimal with an
+    /// optional fraction and exponent, or `0x` / `0b` / `0o` prefixed integers.
+    fn number(&mut self, first: char) {
+        if first == '0' {
+            let radix = match self.peek() {
+                Some('x') => Some((16, "hexadecimal")),
+                Some('b') => Some((2, "binary")),
+                Some('o') => Some((8, "octal")),
+                _ => None,
+            };
+            if let Some((radix, name)) = radix {
+                self.advance();
+                self.radix_number(radix, name);
+                return;
+            }
         }
 
-        // fractional part
-        if self.peek() == Some('.') && self.peek_next().map(|c| c.is_ascii_digit()).unwrap_or(false)
-        {
+        let mut text = first.to_string();
+        text.push_str(&self.digits(10));
+
+        // fractional part; `1..2` is a range, not a fraction
+        if self.peek() == Some('.') && !matches!(self.peek_next(), Some('.')) {
             self.advance(); // consume '.'
-            while self.peek().map(|c| c.is_ascii_digit()).unwrap_or(false) {
+            if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.error("Expect digits after decimal point.");
+                return;
+            }
+            text.push('.');
+            text.push_str(&self.digits(10));
+        }
+
+        // exponent
+        if let Some(e @ ('e' | 'E')) = self.peek() {
+            self.advance();
+            text.push(e);
+            if let Some(sign @ ('+' | '-')) = self.peek() {
+                self.advance();
+                text.push(sign);
+            }
+            if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.error("Expect digits after exponent.");
+                return;
+            }
+            text.push_str(&self.digits(10));
+        }
+
+        match text.parse::<f64>() {
+            Ok(number) => self.add_literal(TokenType::Number, LiteralValue::Number(number)),
+            Err(_) => self.error(&format!("Invalid number literal '{}'.", text)),
+        }
+    }
+
+    fn radix_number(&mut self, radix: u32, name: &str) {
+        let digits = self.digits(radix);
+        if let Some(c) = self.peek().filter(|c| c.is_ascii_alphanumeric()) {
+            self.error(&format!("Invalid digit '{}' in {} literal.", c, name));
+            while self.peek().is_some_and(Self::is_alphanumeric) {
                 self.advance();
             }
+            return;
+        }
+        if digits.is_empty() {
+            self.error(&format!("Expect digits in {} literal.", name));
+            return;
+        }
+        // Accumulate in f64 rather than u64: values past 2^64 are still representable,
+        // just rounded like any other large number.
+        let value = digits.chars().fold(0.0, |value, digit| {
+            value * radix as f64 + digit.to_digit(radix).unwrap_or(0) as f64
+        });
+        if value.is_finite() {
+            self.add_literal(TokenType::Number, LiteralValue::Number(value));
+        } else {
+            let (first, rest) = name.split_at(1);
+            self.error(&format!(
+                "{}{} literal is too large.",
+                first.to_uppercase(),
+                rest
+            ));
         }
+    }
 
-        let value_str = &self.source[self.start..self.current];
-        let number: f64 = value_str.parse().unwrap_or(0.0);
-        self.add_literal(TokenType::Number, LiteralValue::Number(number));
+    /// Consume a run of digits in `radix`, allowing single `_` separators between digits.
+    /// Returns the digits with the separators removed.
+    fn digits(&mut self, radix: u32) -> String {
+        let mut digits = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_digit(radix) {
+                digits.push(c);
+            } else if c == '_' {
+                let is_digit = |c: Option<char>| c.is_some_and(|c| c.is_digit(radix));
+                let previous = self.char_at(self.current - 1);
+                if !is_digit(previous) || !is_digit(self.peek_next()) {
+                    self.error("Misplaced '_' in number literal.");
+                }
+            } else {
+                break;
+            }
+            self.advance();
+        }
+        digits
     }
 
     fn identifier(&mut self) {
@@ -188,17 +474,40 @@ impl Scanner {
         }
 
         // Determine if identifier is a reserved keyword.
-        let text = &self.source[self.start..self.current];
-        let typ = match text {
+        let text = self.lexeme();
+        let typ = match text.as_str() {
             "true" => TokenType::True,
             "false" => TokenType::False,
             "nil" => TokenType::Nil,
             "print" => TokenType::Print,
             "var" => TokenType::Var,
+            "const" => TokenType::Const,
+            "if" => TokenType::If,
+            "else" => TokenType::Else,
+            "while" => TokenType::While,
+            "for" => TokenType::For,
+            "and" => TokenType::And,
+            "or" => TokenType::Or,
+            "fun" => TokenType::Fun,
+            "return" => TokenType::Return,
+            "class" => TokenType::Class,
+            "this" => TokenType::This,
+            "super" => TokenType::Super,
+            "break" => TokenType::Break,
+            "continue" => TokenType::Continue,
+            "div" => TokenType::Div,
+            "match" => TokenType::Match,
+            "throw" => TokenType::Throw,
+            "try" => TokenType::Try,
+            "catch" => TokenType::Catch,
+            "finally" => TokenType::Finally,
+            "import" => TokenType::Import,
+            "as" => TokenType::As,
+            "export" => TokenType::Export,
             _ => TokenType::Identifier,
         };
 
-        self.tokens.push(Token::simple(typ, text, self.line));
+        self.tokens.push(Token::simple(typ, &text, self.line));
     }
 
     fn is_at_end(&self) -> bool {
@@ -210,12 +519,166 @@ impl Scanner {
         eprintln!("[line {}] Error: {}", self.line, message);
     }
 
-    // Helper character classification functions.
+    // Helper character classification functions. Identifiers may use any Unicode letter.
     fn is_alpha(c: char) -> bool {
-        c.is_ascii_alphabetic() || c == '_'
+        c.is_alphabetic() || c == '_'
     }
 
     fn is_alphanumeric(c: char) -> bool {
-        c.is_ascii_alphanumeric() || c == '_'
+        c.is_alphanumeric() || c == '_'
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan_string(source: &str) -> String {
+        let mut scanner = Scanner::new(source.to_string());
+        let tokens = scanner.scan_tokens();
+        assert!(!scanner.has_error(), "unexpected scan error in {}", source);
+        match &tokens[0].literal {
+            Some(LiteralValue::String(s)) => s.clone(),
+            other => panic!("expected a string literal, got {:?}", other),
+        }
+    }
+
+    fn scan_has_error(source: &str) -> bool {
+        let mut scanner = Scanner::new(source.to_string());
+        scanner.scan_tokens();
+        scanner.has_error()
+    }
+
+    fn scan_number(source: &str) -> f64 {
+        let mut scanner = Scanner::new(source.to_string());
+        let tokens = scanner.scan_tokens();
+        assert!(!scanner.has_error(), "unexpected scan error in {}", source);
+        match tokens[0].literal {
+            Some(LiteralValue::Number(n)) => n,
+            ref other => panic!("expected a number literal, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn scans_prefixed_and_separated_numbers() {
+        assert_eq!(scan_number("0xFF"), 255.0);
+        assert_eq!(scan_number("0b1010"), 10.0);
+        assert_eq!(scan_number("0o17"), 15.0);
+        assert_eq!(scan_number("1_000_000"), 1_000_000.0);
+        assert_eq!(scan_number("0xdead_beef"), 3_735_928_559.0);
+        assert_eq!(scan_number("2.718_5"), 2.7185);
+        assert_eq!(scan_number("0xFFFFFFFFFFFFFFFFF"), 2f64.powi(68));
+        assert_eq!(scan_number("0x1_0000_0000_0000_0000"), 2f64.powi(64));
+    }
+
+    #[test]
+    fn scans_scientific_notation() {
+        assert_eq!(scan_number("1.5e-3"), 1.5e-3);
+        assert_eq!(scan_number("2E10"), 2e10);
+        assert_eq!(scan_number("7e+2"), 700.0);
+    }
+
+    #[test]
+    fn reports_malformed_numbers() {
+        assert!(scan_has_error(".5"));
+        assert!(scan_has_error("0x"));
+        assert!(scan_has_error("0b102"));
+        assert!(scan_has_error("0xFG"));
+        assert!(scan_has_error("1__000"));
+        assert!(scan_has_error("1000_"));
+        assert!(scan_has_error("0x_1"));
+        assert!(scan_has_error(&format!("0x{}", "F".repeat(300))));
+        assert!(scan_has_error("1e"));
+        assert!(scan_has_error("1e+"));
+        assert!(scan_has_error("2E-x"));
+        assert!(scan_has_error("1."));
+        assert!(scan_has_error("1.;"));
+        assert!(!scan_has_error("1..5"));
+    }
+
+    #[test]
+    fn decodes_escape_sequences() {
+        assert_eq!(scan_string(r#""a\nb\tc\r\0""#), "a\nb\tc\r\0");
+        assert_eq!(scan_string(r#""say \"hi\" \\ bye""#), "say \"hi\" \\ bye");
+        assert_eq!(scan_string(r#""cost: \${x}""#), "cost: ${x}");
+    }
+
+    #[test]
+    fn decodes_unicode_escapes() {
+        assert_eq!(scan_string(r#""\u{48}\u{e9}\u{1F600}""#), "H\u{e9}\u{1F600}");
+    }
+
+    #[test]
+    fn raw_strings_keep_backslashes_and_holes() {
+        assert_eq!(scan_string(r#"r"C:\new\${x}""#), r"C:\new\${x}");
+    }
+
+    #[test]
+    fn reports_invalid_escapes() {
+        assert!(scan_has_error(r#""\q""#));
+        assert!(scan_has_error(r#""\u{110000}""#));
+        assert!(scan_has_error(r#""\u{}""#));
+        assert!(scan_has_error(r#""\u41""#));
+    }
+
+    #[test]
+    fn scans_strings_with_non_ascii_text() {
+        assert_eq!(scan_string("\"café 😀 naïve\""), "café 😀 naïve");
+        assert_eq!(scan_string("\"😀${x}\""), "😀");
+
+        let mut scanner = Scanner::new("\"héllo\" + \"🎉\";".to_string());
+        let tokens = scanner.scan_tokens();
+        let lexemes: Vec<&str> = tokens.iter().map(|t| t.lexeme.as_str()).collect();
+        assert_eq!(lexemes, ["\"héllo\"", "+", "\"🎉\"", ";", ""]);
+    }
+
+    #[test]
+    fn skips_comments_with_non_ascii_text() {
+        let mut scanner = Scanner::new("// déjà vu 🚀\nprint 1;".to_string());
+        let tokens = scanner.scan_tokens();
+        assert!(!scanner.has_error());
+        assert_eq!(tokens[0].typ, TokenType::Print);
+        assert_eq!(tokens[0].line, 2);
+    }
+
+    #[test]
+    fn scans_unicode_identifiers() {
+        let mut scanner = Scanner::new("var café = größe_2;".to_string());
+        let tokens = scanner.scan_tokens();
+        assert!(!scanner.has_error());
+        assert_eq!(tokens[1].typ, TokenType::Identifier);
+        assert_eq!(tokens[1].lexeme, "café");
+        assert_eq!(tokens[3].lexeme, "größe_2");
+    }
+
+    #[test]
+    fn reports_stray_emoji_without_panicking() {
+        assert!(scan_has_error("var x = 😀;"));
+    }
+
+    #[test]
+    fn skips_nested_block_comments() {
+        let source = "/* outer /* inner\n */ still comment */ print 1; /**/ /* a\nb */ 2";
+        let mut scanner = Scanner::new(source.to_string());
+        let tokens = scanner.scan_tokens();
+        assert!(!scanner.has_error());
+        let types: Vec<_> = tokens.iter().map(|t| (t.typ, t.line)).collect();
+        assert_eq!(
+            types,
+            [
+                (TokenType::Print, 2),
+                (TokenType::Number, 2),
+                (TokenType::SemiColon, 2),
+                (TokenType::Number, 3),
+                (TokenType::Eof, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unterminated_block_comment() {
+        assert!(scan_has_error("print 1;\n/* open /* nested */\nnever closed"));
+        assert!(scan_has_error("/* *"));
+        assert!(!scan_has_error("1 /* * / */ / 2"));
     }
 }
@@ -1,4 +1,11 @@
+use std::cell::RefCell;
 use std::fmt::Display;
+use std::rc::Rc;
+
+use crate::callable::{LoxFunction, NativeFunction};
+use crate::class::{LoxClass, LoxInstance};
+use crate::map::LoxMap;
+use crate::module::LoxModule;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TokenType {
@@ -7,19 +14,35 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
+    Colon,
+    Question,
     Dot,
+    DotDot,
+    DotDotEqual,
+    Pipe,
     Minus,
+    MinusEqual,
+    MinusMinus,
     Plus,
+    PlusEqual,
+    PlusPlus,
     SemiColon,
     Star,
+    StarStar,
+    StarEqual,
     Slash,
+    SlashEqual,
+    Percent,
 
     // One or two character tokens.
     Bang,
     BangEqual,
     Equal,
     EqualEqual,
+    Arrow,
     Less,
     LessEqual,
     Greater,
@@ -28,6 +51,8 @@ pub enum TokenType {
     // Literals.
     Identifier,
     String,
+    /// A string segment that ends at a `${`; the embedded expression's tokens follow it.
+    Interpolation,
     Number,
 
     // Keywords.
@@ -36,17 +61,72 @@ pub enum TokenType {
     Nil,
     Print,
     Var,
+    Const,
+    If,
+    Else,
+    While,
+    For,
+    And,
+    Or,
+    Fun,
+    Return,
+    Class,
+    This,
+    Super,
+    Break,
+    Continue,
+    Div,
+    Match,
+    Throw,
+    Try,
+    Catch,
+    Finally,
+    Import,
+    As,
+    Export,
 
     // End of file.
     Eof,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub enum LiteralValue {
     Number(f64),
     String(String),
     Boolean(bool),
     Nil,
+    Function(Rc<LoxFunction>),
+    NativeFunction(Rc<NativeFunction>),
+    Class(Rc<LoxClass>),
+    Instance(Rc<RefCell<LoxInstance>>),
+    /// A mutable list shared by every value that refers to it.
+    List(Rc<RefCell<Vec<LiteralValue>>>),
+    /// A mutable, insertion-ordered map keyed by hashable values.
+    Map(Rc<RefCell<LoxMap>>),
+    Module(Rc<LoxModule>),
+    /// An immutable sequence built by `(a, b)`, mainly for destructuring.
+    Tuple(Rc<Vec<LiteralValue>>),
+}
+
+impl PartialEq for LiteralValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (LiteralValue::Number(l), LiteralValue::Number(r)) => l == r,
+            (LiteralValue::String(l), LiteralValue::String(r)) => l == r,
+            (LiteralValue::Boolean(l), LiteralValue::Boolean(r)) => l == r,
+            (LiteralValue::Nil, LiteralValue::Nil) => true,
+            (LiteralValue::Tuple(l), LiteralValue::Tuple(r)) => l == r,
+            // Callables, instances, lists, maps and modules compare by identity.
+            (LiteralValue::Function(l), LiteralValue::Function(r)) => Rc::ptr_eq(l, r),
+            (LiteralValue::NativeFunction(l), LiteralValue::NativeFunction(r)) => Rc::ptr_eq(l, r),
+            (LiteralValue::Class(l), LiteralValue::Class(r)) => Rc::ptr_eq(l, r),
+            (LiteralValue::Instance(l), LiteralValue::Instance(r)) => Rc::ptr_eq(l, r),
+            (LiteralValue::List(l), LiteralValue::List(r)) => Rc::ptr_eq(l, r),
+            (LiteralValue::Map(l), LiteralValue::Map(r)) => Rc::ptr_eq(l, r),
+            (LiteralValue::Module(l), LiteralValue::Module(r)) => Rc::ptr_eq(l, r),
+            _ => false,
+        }
+    }
 }
 
 impl LiteralValue {
@@ -63,13 +143,78 @@ impl Display for LiteralValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             LiteralValue::Number(n) => write!(f, "{}", n),
+            // The alternate form `{:#}` leaves strings unquoted, at any depth.
+            LiteralValue::String(s) if f.alternate() => write!(f, "{}", s),
             LiteralValue::String(s) => write!(f, "\"{}\"", s),
             LiteralValue::Boolean(b) => write!(f, "{}", b),
             LiteralValue::Nil => write!(f, "nil"),
+            LiteralValue::Function(function) => write!(f, "{}", function),
+            LiteralValue::NativeFunction(function) => write!(f, "{}", function),
+            LiteralValue::Class(class) => write!(f, "{}", class),
+            LiteralValue::Instance(instance) => write!(f, "{}", instance.borrow()),
+            LiteralValue::List(list) => write_once(f, Rc::as_ptr(list).cast(), "[...]", |f| {
+                write!(f, "[")?;
+                for (i, element) in list.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_element(f, element)?;
+                }
+                write!(f, "]")
+            }),
+            LiteralValue::Map(map) => write_once(f, Rc::as_ptr(map).cast(), "{...}", |f| {
+                if f.alternate() {
+                    write!(f, "{:#}", map.borrow())
+                } else {
+                    write!(f, "{}", map.borrow())
+                }
+            }),
+            LiteralValue::Module(module) => write!(f, "{}", module),
+            LiteralValue::Tuple(elements) => {
+                write!(f, "(")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_element(f, element)?;
+                }
+                write!(f, ")")
+            }
         }
     }
 }
 
+/// Write a value nested inside a list, map or tuple, keeping the caller's `{:#}` flag.
+pub fn write_element(f: &mut std::fmt::Formatter<'_>, value: &LiteralValue) -> std::fmt::Result {
+    if f.alternate() {
+        write!(f, "{:#}", value)
+    } else {
+        write!(f, "{}", value)
+    }
+}
+
+thread_local! {
+    /// Lists and maps whose contents are being formatted right now, outermost first.
+    static FORMATTING: RefCell<Vec<*const ()>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Format a list or map with `body`, or write `placeholder` if it is already being
+/// formatted further out, so a container that holds itself doesn't recurse forever.
+fn write_once(
+    f: &mut std::fmt::Formatter<'_>,
+    container: *const (),
+    placeholder: &str,
+    body: impl FnOnce(&mut std::fmt::Formatter<'_>) -> std::fmt::Result,
+) -> std::fmt::Result {
+    if FORMATTING.with_borrow(|formatting| formatting.contains(&container)) {
+        return write!(f, "{}", placeholder);
+    }
+    FORMATTING.with_borrow_mut(|formatting| formatting.push(container));
+    let result = body(f);
+    FORMATTING.with_borrow_mut(|formatting| formatting.pop());
+    result
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Token {
     pub typ: TokenType,
@@ -108,29 +253,69 @@ impl std::fmt::Display for TokenType {
             RightParen => ")",
             LeftBrace => "{",
             RightBrace => "}",
+            LeftBracket => "[",
+            RightBracket => "]",
             Comma => ",",
+            Colon => ":",
+            Question => "?",
             Dot => ".",
+            DotDot => "..",
+            DotDotEqual => "..=",
+            Pipe => "|",
             Minus => "-",
+            MinusEqual => "-=",
+            MinusMinus => "--",
             Plus => "+",
+            PlusEqual => "+=",
+            PlusPlus => "++",
             SemiColon => ";",
             Star => "*",
+            StarStar => "**",
+            StarEqual => "*=",
             Slash => "/",
+            SlashEqual => "/=",
+            Percent => "%",
             Bang => "!",
             BangEqual => "!=",
             Equal => "=",
             EqualEqual => "==",
+            Arrow => "=>",
             Less => "<",
             LessEqual => "<=",
             Greater => ">",
             GreaterEqual => ">=",
             Identifier => "identifier",
             String => "string",
+            Interpolation => "string interpolation",
             Number => "number",
             True => "true",
             False => "false",
             Nil => "nil",
             Print => "print",
             Var => "var",
+            Const => "const",
+            If => "if",
+            Else => "else",
+            While => "while",
+            For => "for",
+            And => "and",
+            Or => "or",
+            Fun => "fun",
+            Return => "return",
+            Class => "class",
+            This => "this",
+            Super => "super",
+            Break => "break",
+            Continue => "continue",
+            Div => "div",
+            Match => "match",
+            Throw => "throw",
+            Try => "try",
+            Catch => "catch",
+            Finally => "finally",
+            Import => "import",
+            As => "as",
+            Export => "export",
             Eof => "EOF",
         };
         write!(f, "{}", s)